@@ -0,0 +1,227 @@
+// backend/rust_honeypots/src/auth_decoy.rs
+//
+// `is_attack_request` flaggt `login`, `wp-login`, `admin`, `phpmyadmin` & Co. bereits als
+// verdächtig, aber der Honeypot stellt nie eine echte Auth-Challenge und lässt damit jedes
+// Credential-Stuffing unbemerkt durchlaufen. Dieses Modul bringt eine kleine Auth-Schicht
+// nach dem Vorbild von gotham_restfuls `AuthSource`/`AuthStatus`: `AuthSource`-Implementierungen
+// extrahieren Zugangsdaten aus Basic-/Bearer-Headern oder Login-Formularen, ohne sie jemals
+// zu validieren - jeder Versuch wird nur als strukturierter `credential_attempt` protokolliert
+// und landet in einer Zählung pro Quell-IP, damit Brute-Force-Muster im Log sichtbar werden.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// Pfadmuster, die als "geschützt" gelten und eine Auth-Challenge statt der normalen
+// Decoy-Antwort auslösen. Überschneidet sich bewusst mit der Login-/Admin-Teilmenge von
+// `is_attack_request`s `suspicious_paths`.
+const PROTECTED_PATH_PATTERNS: &[&str] = &[
+    "login", "wp-login", "admin", "wp-admin", "administrator",
+    "phpmyadmin", "cpanel", "webmail",
+];
+
+pub fn is_protected_path(path: &str) -> bool {
+    let path_lower = path.to_lowercase();
+    PROTECTED_PATH_PATTERNS.iter().any(|pattern| path_lower.contains(pattern))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum AuthScheme {
+    Basic,
+    Bearer,
+    Form,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialAttempt {
+    pub scheme: AuthScheme,
+    pub username: Option<String>,
+    // Passwort (Basic/Form) oder Bearer-Token - absichtlich ungefiltert mitgeloggt, da genau
+    // das der Zweck dieses Honeypots ist.
+    pub secret: Option<String>,
+    pub attempts_from_ip: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Ergebnis einer Auth-Prüfung gegen einen geschützten Pfad. `attempt` ist `None`, solange der
+// Angreifer noch keine Zugangsdaten mitgeschickt hat (erste 401-Challenge).
+#[derive(Debug, Clone)]
+pub struct AuthChallenge {
+    pub attempt: Option<CredentialAttempt>,
+}
+
+// Analog zu gotham_restfuls `AuthSource`: extrahiert Zugangsdaten aus einer Anfrage. Eine
+// Validierung gibt es bewusst nicht - dieser Honeypot lehnt jeden Versuch ab.
+trait AuthSource {
+    fn extract(headers: &HeaderMap, form_body: Option<&Value>) -> Option<CredentialAttempt>;
+}
+
+struct BasicAuthSource;
+impl AuthSource for BasicAuthSource {
+    fn extract(headers: &HeaderMap, _form_body: Option<&Value>) -> Option<CredentialAttempt> {
+        let header = headers.get(axum::http::header::AUTHORIZATION)?.to_str().ok()?;
+        let encoded = header.strip_prefix("Basic ")?;
+        let decoded = general_purpose::STANDARD.decode(encoded).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some(CredentialAttempt {
+            scheme: AuthScheme::Basic,
+            username: Some(username.to_string()),
+            secret: Some(password.to_string()),
+            attempts_from_ip: 0,
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+struct BearerAuthSource;
+impl AuthSource for BearerAuthSource {
+    fn extract(headers: &HeaderMap, _form_body: Option<&Value>) -> Option<CredentialAttempt> {
+        let header = headers.get(axum::http::header::AUTHORIZATION)?.to_str().ok()?;
+        let token = header.strip_prefix("Bearer ")?;
+        Some(CredentialAttempt {
+            scheme: AuthScheme::Bearer,
+            username: None,
+            secret: Some(token.to_string()),
+            attempts_from_ip: 0,
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+struct FormAuthSource;
+impl AuthSource for FormAuthSource {
+    fn extract(_headers: &HeaderMap, form_body: Option<&Value>) -> Option<CredentialAttempt> {
+        let form = form_body?.as_object()?;
+        let username = ["username", "user", "email", "login"]
+            .iter()
+            .find_map(|key| form.get(*key))
+            .and_then(Value::as_str);
+        let password = ["password", "pass", "pwd"]
+            .iter()
+            .find_map(|key| form.get(*key))
+            .and_then(Value::as_str);
+
+        if username.is_none() && password.is_none() {
+            return None;
+        }
+
+        Some(CredentialAttempt {
+            scheme: AuthScheme::Form,
+            username: username.map(str::to_string),
+            secret: password.map(str::to_string),
+            attempts_from_ip: 0,
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+// Zählt Credential-Versuche pro Quell-IP, um Brute-Force-/Credential-Stuffing-Muster im Log
+// sichtbar zu machen.
+static ATTEMPTS_BY_IP: Lazy<Arc<Mutex<HashMap<String, u32>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+fn record_attempt(source_ip: &str) -> u32 {
+    let mut attempts = ATTEMPTS_BY_IP.lock().unwrap();
+    let count = attempts.entry(source_ip.to_string()).or_insert(0);
+    *count += 1;
+    *count
+}
+
+// Prüft eine Anfrage gegen die konfigurierten geschützten Pfade. Liefert `None`, wenn der
+// Pfad nicht geschützt ist (normale Decoy-Antwort bleibt unverändert), sonst eine
+// `AuthChallenge` mit dem erkannten Credential-Versuch, falls einer mitgeschickt wurde.
+pub fn evaluate(path: &str, source_ip: &str, headers: &HeaderMap, form_body: Option<&Value>) -> Option<AuthChallenge> {
+    if !is_protected_path(path) {
+        return None;
+    }
+
+    let attempt = BasicAuthSource::extract(headers, form_body)
+        .or_else(|| BearerAuthSource::extract(headers, form_body))
+        .or_else(|| FormAuthSource::extract(headers, form_body))
+        .map(|mut attempt| {
+            attempt.attempts_from_ip = record_attempt(source_ip);
+            attempt
+        });
+
+    Some(AuthChallenge { attempt })
+}
+
+const WWW_AUTHENTICATE_REALM: &str = r#"Basic realm="Restricted Area""#;
+
+fn looks_like_html_login(path: &str) -> bool {
+    let path_lower = path.to_lowercase();
+    !(path_lower.starts_with("/api/") || path_lower.starts_with("/rest/") || path_lower.starts_with("/v1/") || path_lower.starts_with("/v2/"))
+}
+
+// hyper/httparse erlauben unkodierte `"`/`<`/`>`-Bytes im Request-Target - ungeescaped in ein
+// HTML-Attribut eingesetzt, bricht der Pfad aus `action="..."` aus und injiziert Markup (siehe
+// dieselbe Klasse Bug, bereits für den JSON-Body in `decoy.rs::render_500` gefixt).
+fn escape_html_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn render_login_form(path: &str, invalid_attempt: bool) -> String {
+    let error_message = if invalid_attempt {
+        r#"<p class="error">Benutzername oder Passwort ist falsch.</p>"#
+    } else {
+        ""
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><title>Anmeldung erforderlich</title></head>
+<body>
+<h1>Anmeldung erforderlich</h1>
+<form method="POST" action="{path}">
+    <label>Benutzername: <input type="text" name="username"></label><br>
+    <label>Passwort: <input type="password" name="password"></label><br>
+    <button type="submit">Anmelden</button>
+</form>
+{error_message}
+</body></html>"#,
+        path = escape_html_attribute(path),
+        error_message = error_message
+    )
+}
+
+// Baut die Antwort auf eine Auth-Challenge: immer eine Ablehnung - `401` mit
+// `WWW-Authenticate`, solange noch keine Zugangsdaten vorliegen, sonst `403`, nachdem ein
+// Versuch protokolliert wurde. HTML-Login-Pfade bekommen ein passendes Formular, der Rest
+// eine knappe JSON-Fehlermeldung.
+pub fn challenge_response(path: &str, has_attempt: bool) -> (StatusCode, HeaderMap, String) {
+    let mut headers = HeaderMap::new();
+    headers.insert(axum::http::header::WWW_AUTHENTICATE, HeaderValue::from_static(WWW_AUTHENTICATE_REALM));
+
+    let status = if has_attempt { StatusCode::FORBIDDEN } else { StatusCode::UNAUTHORIZED };
+
+    if looks_like_html_login(path) {
+        headers.insert(axum::http::header::CONTENT_TYPE, HeaderValue::from_static("text/html; charset=utf-8"));
+        (status, headers, render_login_form(path, has_attempt))
+    } else {
+        headers.insert(axum::http::header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let body = if has_attempt {
+            json!({"error": "invalid_credentials"})
+        } else {
+            json!({"error": "unauthorized"})
+        };
+        (status, headers, body.to_string())
+    }
+}
+
+// Simuliert die Latenz eines echten Auth-Backends (DB-Lookup + Passwort-Hash-Vergleich),
+// statt sofort abzulehnen - eine Antwort in unter 1ms würde jeden halbwegs aufmerksamen
+// Credential-Stuffer sofort stutzig machen.
+pub async fn simulate_auth_delay() {
+    tokio::time::sleep(std::time::Duration::from_millis(180)).await;
+}
@@ -1,10 +1,12 @@
 // backend/rust_honeypots/src/common.rs
 
 use reqwest::Client;
-use std::net::IpAddr;
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
+use crate::http_client::HttpClientOptions;
+use crate::log_sink::LogSink;
+
 // Datenstruktur für den HTTP-Client und Konfiguration
 // Muss 'Send + Sync' sein, damit sie sicher über Async-Tasks geteilt werden kann
 #[derive(Clone)]
@@ -13,6 +15,26 @@ pub struct AppState { // 'pub' damit es von anderen Modulen importiert werden ka
     pub supabase_api_url: String,
     pub supabase_service_role_key: String,
     pub python_ai_url: String,
+    // Eines oder mehrere LogSink-Backends, an die Interaktionen weitergeleitet werden
+    // (Supabase, Postgres, lokale Datei, Redis-Queue, ...).
+    pub log_sinks: Vec<Arc<dyn LogSink>>,
+    // Timeout/Retry-Konfiguration für `http_client` (Supabase & KI-Forwarding), siehe
+    // `HttpClientOptions::from_env`.
+    pub http_client_options: HttpClientOptions,
+    // Memory-gemappter Reader der lokalen GeoLite2-City-Datenbank (siehe `geoip`), sofern
+    // `GEOIP_CITY_DB_PATH` konfiguriert ist - `None` lässt `geoip::lookup_geoip` direkt auf den
+    // HTTP-Fallback zurückfallen.
+    pub geoip_reader: Option<Arc<maxminddb::Reader<Vec<u8>>>>,
+    // Optionaler Reader der separaten GeoLite2-ASN/ISP-Datenbank für `isp`/`organization`.
+    pub geoip_isp_reader: Option<Arc<maxminddb::Reader<Vec<u8>>>>,
+    // LRU+TTL-Cache vor `geoip::lookup_geoip`, siehe `geoip::GEOIP_CACHE_CAPACITY`/`_TTL`.
+    pub geoip_cache: Arc<crate::geoip::GeoIpCache>,
+    // Austauschbares HTTP-Backend für GeoIP (ip-api.com oder ipgeolocation.io), siehe
+    // `geoip::GeoIpProvider`/`geoip::build_provider`.
+    pub geoip_provider: Arc<dyn crate::geoip::GeoIpProvider>,
+    // Drosselt ausgehende GeoIP-Anfragen auf die Quote des Providers, siehe
+    // `geoip::build_rate_limiter`/`geoip::GEOIP_RATE_LIMIT_PER_MINUTE`.
+    pub geoip_rate_limiter: Arc<crate::geoip::GeoIpRateLimiter>,
 }
 
 // Typedef für den gemeinsam genutzten State
@@ -50,56 +72,3 @@ impl Default for GeoLocation {
     }
 }
 
-// GeoIP lookup using ip-api.com (free service)
-pub async fn lookup_geoip(ip: IpAddr, http_client: &Client) -> GeoLocation {
-    // Skip private/local IP addresses
-    let is_private = match ip {
-        IpAddr::V4(ipv4) => ipv4.is_private() || ipv4.is_loopback() || ipv4.is_multicast(),
-        IpAddr::V6(ipv6) => ipv6.is_loopback() || ipv6.is_multicast() || ipv6.is_unspecified(),
-    };
-    
-    if is_private {
-        println!("Skipping GeoIP lookup for private/local IP: {}", ip);
-        return GeoLocation::default();
-    }
-
-    let url = format!("http://ip-api.com/json/{}?fields=status,message,country,countryCode,region,regionName,city,lat,lon,timezone,isp,org", ip);
-    
-    println!("Looking up GeoIP for: {}", ip);
-    
-    match http_client.get(&url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                if let Ok(geo_data) = response.json::<serde_json::Value>().await {
-                    if geo_data.get("status").and_then(|s| s.as_str()) == Some("success") {
-                        let location = GeoLocation {
-                            country_code: geo_data.get("countryCode").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            country_name: geo_data.get("country").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            region_code: geo_data.get("region").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            region_name: geo_data.get("regionName").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            city: geo_data.get("city").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            latitude: geo_data.get("lat").and_then(|v| v.as_f64()),
-                            longitude: geo_data.get("lon").and_then(|v| v.as_f64()),
-                            timezone: geo_data.get("timezone").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            isp: geo_data.get("isp").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                            organization: geo_data.get("org").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                        };
-                        println!("GeoIP lookup successful: {:?}", location);
-                        return location;
-                    } else {
-                        println!("GeoIP API returned error status for {}: {:?}", ip, geo_data);
-                    }
-                } else {
-                    println!("Failed to parse GeoIP response as JSON for {}", ip);
-                }
-            } else {
-                println!("GeoIP API request failed with status: {} for {}", response.status(), ip);
-            }
-        }
-        Err(e) => {
-            eprintln!("GeoIP lookup failed for {}: {:?}", ip, e);
-        }
-    }
-    
-    GeoLocation::default()
-}
\ No newline at end of file
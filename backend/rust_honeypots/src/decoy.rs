@@ -0,0 +1,252 @@
+// backend/rust_honeypots/src/decoy.rs
+//
+// `http_honeypot.rs` rendert bislang für jede Anfrage dieselbe fest codierte, deutsche
+// "Interner Fehler"-Seite - ein Scanner sieht beim ersten Request also schon, dass er es
+// mit einem Honeypot statt mit echter Software zu tun hat. Dieses Modul bringt eine
+// Templating-Schicht nach dem Vorbild von Kittybox' `markup`-Modul: jedes `DecoyProfile`
+// liefert eigene Index-/404-/500-Templates sowie die dazu passenden `Server`-,
+// `X-Powered-By`- und `Set-Cookie`-Header, sodass ein WordPress-Scanner plausibles
+// WP-Markup sieht und ein rohes curl eine nginx-Default-Seite.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecoyProfile {
+    Apache,
+    Nginx,
+    Iis,
+    WordPress,
+    GenericApi,
+}
+
+impl DecoyProfile {
+    // Wählt ein Profil: WordPress-Pfade (`wp-`, `wordpress`, `xmlrpc.php`) ergeben immer das
+    // WordPress-Profil, sonst entscheidet der von der Fingerprinting-Pipeline erkannte
+    // `scanner_type` und, falls kein Scanner erkannt wurde, der angefragte Pfad.
+    pub fn select(scanner_type: Option<&str>, request_path: &str) -> Self {
+        let path_lower = request_path.to_lowercase();
+        if path_lower.contains("wp-") || path_lower.contains("wordpress") || path_lower.contains("xmlrpc.php") {
+            return DecoyProfile::WordPress;
+        }
+
+        match scanner_type {
+            Some("Nikto") | Some("Gobuster") => DecoyProfile::Apache,
+            Some("SQLMap") | Some("Burp Suite") => DecoyProfile::GenericApi,
+            Some("Nmap") | Some("Masscan") | Some("Generic Scanner/Bot") => DecoyProfile::Nginx,
+            _ => {
+                if path_lower.starts_with("/api/") || path_lower.starts_with("/v1/") || path_lower.starts_with("/v2/") {
+                    DecoyProfile::GenericApi
+                } else {
+                    DecoyProfile::Nginx
+                }
+            }
+        }
+    }
+
+    fn server_header(&self) -> &'static str {
+        match self {
+            DecoyProfile::Apache | DecoyProfile::WordPress => "Apache/2.4.52 (Ubuntu)",
+            DecoyProfile::Nginx | DecoyProfile::GenericApi => "nginx/1.18.0 (Ubuntu)",
+            DecoyProfile::Iis => "Microsoft-IIS/10.0",
+        }
+    }
+
+    fn powered_by_header(&self) -> Option<&'static str> {
+        match self {
+            DecoyProfile::Iis => Some("ASP.NET"),
+            DecoyProfile::WordPress => Some("PHP/8.1.2"),
+            DecoyProfile::GenericApi => Some("Express"),
+            DecoyProfile::Apache | DecoyProfile::Nginx => None,
+        }
+    }
+
+    fn set_cookie_header(&self) -> Option<&'static str> {
+        match self {
+            DecoyProfile::Iis => Some("ASPSESSIONIDACBQDRTS=KIPEFJBBOCKPGGADFNKCOKNB; path=/"),
+            DecoyProfile::WordPress => Some("wordpress_test_cookie=WP+Cookie+check; path=/"),
+            DecoyProfile::Apache | DecoyProfile::Nginx | DecoyProfile::GenericApi => None,
+        }
+    }
+
+    // Setzt `Server`, ggf. `X-Powered-By` und ggf. `Set-Cookie` passend zum Profil. Fehler
+    // beim Header-Bau werden verschluckt - ein fehlender Deko-Header ist kein Grund, die
+    // Antwort an den Angreifer scheitern zu lassen.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(self.server_header()) {
+            headers.insert(HeaderName::from_static("server"), value);
+        }
+        if let Some(powered_by) = self.powered_by_header() {
+            if let Ok(value) = HeaderValue::from_str(powered_by) {
+                headers.insert(HeaderName::from_static("x-powered-by"), value);
+            }
+        }
+        if let Some(cookie) = self.set_cookie_header() {
+            if let Ok(value) = HeaderValue::from_str(cookie) {
+                headers.insert(axum::http::header::SET_COOKIE, value);
+            }
+        }
+    }
+
+    // Die Hauptseite, in die der von der KI gelieferte Desinformationstext und das
+    // clientseitige Fingerprinting-Skript eingefügt werden.
+    pub fn render_index(&self, disinformation: &str, fingerprinting_script: &str) -> String {
+        // `GENERIC_API_INDEX` ist ein JSON-Body - Escaping wie in `render_500`.
+        let disinformation = if matches!(self, DecoyProfile::GenericApi) {
+            let escaped_json_string = serde_json::to_string(disinformation).unwrap_or_else(|_| "\"\"".to_string());
+            escaped_json_string[1..escaped_json_string.len() - 1].to_string()
+        } else {
+            disinformation.to_string()
+        };
+        self.index_template()
+            .replace("{{disinformation}}", &disinformation)
+            .replace("{{fingerprinting_script}}", fingerprinting_script)
+    }
+
+    pub fn render_404(&self) -> String {
+        self.not_found_template().to_string()
+    }
+
+    pub fn render_500(&self, disinformation: &str) -> String {
+        let template = self.server_error_template();
+        if matches!(self, DecoyProfile::GenericApi) {
+            // `GENERIC_API_500` ist ein JSON-Body - naives String-Replace würde bei `"`/`\` im
+            // KI-generierten Text ungültiges JSON erzeugen. `serde_json::to_string` liefert den
+            // Text als JSON-String-Literal inklusive Escaping, die umschließenden Anführungszeichen
+            // werden vor dem Einsetzen wieder entfernt.
+            let escaped_json_string = serde_json::to_string(disinformation).unwrap_or_else(|_| "\"\"".to_string());
+            let escaped = &escaped_json_string[1..escaped_json_string.len() - 1];
+            template.replace("{{disinformation}}", escaped)
+        } else {
+            template.replace("{{disinformation}}", disinformation)
+        }
+    }
+
+    fn index_template(&self) -> &'static str {
+        match self {
+            DecoyProfile::Apache => APACHE_INDEX,
+            DecoyProfile::Nginx => NGINX_INDEX,
+            DecoyProfile::Iis => IIS_INDEX,
+            DecoyProfile::WordPress => WORDPRESS_INDEX,
+            DecoyProfile::GenericApi => GENERIC_API_INDEX,
+        }
+    }
+
+    fn not_found_template(&self) -> &'static str {
+        match self {
+            DecoyProfile::Apache => APACHE_404,
+            DecoyProfile::Nginx => NGINX_404,
+            DecoyProfile::Iis => IIS_404,
+            DecoyProfile::WordPress => WORDPRESS_404,
+            DecoyProfile::GenericApi => GENERIC_API_404,
+        }
+    }
+
+    fn server_error_template(&self) -> &'static str {
+        match self {
+            DecoyProfile::Apache => APACHE_500,
+            DecoyProfile::Nginx => NGINX_500,
+            DecoyProfile::Iis => IIS_500,
+            DecoyProfile::WordPress => WORDPRESS_500,
+            DecoyProfile::GenericApi => GENERIC_API_500,
+        }
+    }
+}
+
+const APACHE_INDEX: &str = r#"<!DOCTYPE html>
+<html><head><title>Apache2 Ubuntu Default Page: It works</title></head>
+<body><h1>It works!</h1>
+<p>This is the default welcome page used to test the correct operation of the Apache2 server after installation on Ubuntu systems.</p>
+<p>{{disinformation}}</p>
+{{fingerprinting_script}}
+</body></html>"#;
+
+const APACHE_404: &str = r#"<!DOCTYPE html>
+<html><head><title>404 Not Found</title></head>
+<body><h1>Not Found</h1>
+<p>The requested URL was not found on this server.</p>
+<hr><address>Apache/2.4.52 (Ubuntu) Server</address>
+</body></html>"#;
+
+const APACHE_500: &str = r#"<!DOCTYPE html>
+<html><head><title>500 Internal Server Error</title></head>
+<body><h1>Internal Server Error</h1>
+<p>The server encountered an internal error and was unable to complete your request.</p>
+<p>{{disinformation}}</p>
+<hr><address>Apache/2.4.52 (Ubuntu) Server</address>
+</body></html>"#;
+
+const NGINX_INDEX: &str = r#"<!DOCTYPE html>
+<html><head><title>Welcome to nginx!</title></head>
+<body>
+<h1>Welcome to nginx!</h1>
+<p>If you see this page, the nginx web server is successfully installed and working. Further configuration is required.</p>
+<p>{{disinformation}}</p>
+{{fingerprinting_script}}
+<p><em>Thank you for using nginx.</em></p>
+</body></html>"#;
+
+const NGINX_404: &str = r#"<!DOCTYPE html>
+<html><head><title>404 Not Found</title></head>
+<body><center><h1>404 Not Found</h1></center>
+<hr><center>nginx/1.18.0 (Ubuntu)</center>
+</body></html>"#;
+
+const NGINX_500: &str = r#"<!DOCTYPE html>
+<html><head><title>500 Internal Server Error</title></head>
+<body><center><h1>500 Internal Server Error</h1></center>
+<p>{{disinformation}}</p>
+<hr><center>nginx/1.18.0 (Ubuntu)</center>
+</body></html>"#;
+
+const IIS_INDEX: &str = r#"<!DOCTYPE html>
+<html><head><title>IIS Windows Server</title></head>
+<body><h1>IIS Windows Server</h1>
+<p>The Web Server (IIS) role is installed and running.</p>
+<p>{{disinformation}}</p>
+{{fingerprinting_script}}
+</body></html>"#;
+
+const IIS_404: &str = r#"<!DOCTYPE html>
+<html><head><title>404 - File or directory not found.</title></head>
+<body><h2>HTTP Error 404.0 - Not Found</h2>
+<p>The resource you are looking for has been removed, had its name changed, or is temporarily unavailable.</p>
+</body></html>"#;
+
+const IIS_500: &str = r#"<!DOCTYPE html>
+<html><head><title>500 - Internal server error.</title></head>
+<body><h2>HTTP Error 500.0 - Internal Server Error</h2>
+<p>{{disinformation}}</p>
+</body></html>"#;
+
+const WORDPRESS_INDEX: &str = r#"<!DOCTYPE html>
+<html lang="en-US">
+<head><meta charset="UTF-8"><title>Just another WordPress site</title>
+<link rel='stylesheet' id='wp-block-library-css' href='/wp-includes/css/dist/block-library/style.min.css' type='text/css' media='all' /></head>
+<body class="home wp-singular">
+<header id="masthead"><h1 class="site-title">Just another WordPress site</h1></header>
+<main id="primary">
+<p>{{disinformation}}</p>
+{{fingerprinting_script}}
+</main>
+<footer>Proudly powered by WordPress</footer>
+</body></html>"#;
+
+const WORDPRESS_404: &str = r#"<!DOCTYPE html>
+<html lang="en-US">
+<head><meta charset="UTF-8"><title>Page not found &#8211; Just another WordPress site</title></head>
+<body class="error404">
+<main id="primary"><h1 class="page-title">Nothing Found</h1><p>It looks like nothing was found at this location.</p></main>
+</body></html>"#;
+
+const WORDPRESS_500: &str = r#"<!DOCTYPE html>
+<html lang="en-US">
+<head><meta charset="UTF-8"><title>Error establishing a database connection</title></head>
+<body><h1>Error establishing a database connection</h1>
+<p>{{disinformation}}</p>
+</body></html>"#;
+
+const GENERIC_API_INDEX: &str = r#"{"status":"ok","service":"api-gateway","version":"2.3.1","message":"{{disinformation}}"}"#;
+
+const GENERIC_API_404: &str = r#"{"error":{"code":"not_found","message":"The requested resource was not found."}}"#;
+
+const GENERIC_API_500: &str = r#"{"error":{"code":"internal_error","message":"{{disinformation}}"}}"#;
@@ -0,0 +1,289 @@
+// backend/rust_honeypots/src/fingerprint_analysis.rs
+//
+// `/fingerprint` speichert bisher nur den rohen JSON-Blob aus `collectFingerprint()` - zwei
+// Anfragen vom selben Gerät landen als zwei unabhängige Datensätze, ohne dass sich ein
+// Wiederbesuch erkennen lässt. Dieses Modul leitet daraus, nach dem Vorbild von FingerprintJS,
+// eine stabile `visitor_id` ab: die "stabilen" Komponenten (Canvas/WebGL/Audio/Screen/UA/
+// Plattform/...) werden zu einer geordneten Liste von `key=value`-Strings zusammengefasst und
+// mit MurmurHash3 (x64-128-Bit-Variante) gehasht. "Volatile" Felder wie `timestamp` oder die
+// Reihenfolge von `languages` fließen bewusst nicht ein, damit kleine Drifts die ID nicht kippen.
+
+use serde::Serialize;
+use serde_json::Value;
+
+// Reihenfolge ist Teil des Hash-Inputs und muss daher stabil bleiben.
+const STABLE_SCALAR_KEYS: &[&str] = &["userAgent", "platform", "hardwareConcurrency", "deviceMemory"];
+
+fn stringify_component(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    }
+}
+
+// Baut die geordnete Liste der stabilen `key=value`-Komponenten, über die der Visitor-Hash
+// berechnet wird - Timestamp und Sprachreihenfolge bleiben bewusst außen vor.
+fn stable_components(fingerprint: &Value) -> Vec<String> {
+    let mut components = Vec::new();
+
+    for key in STABLE_SCALAR_KEYS {
+        components.push(format!("{}={}", key, stringify_component(fingerprint.get(*key))));
+    }
+
+    components.push(format!("canvas={}", stringify_component(fingerprint.get("canvas"))));
+
+    if let Some(webgl) = fingerprint.get("webgl") {
+        components.push(format!("webgl.vendor={}", stringify_component(webgl.get("vendor"))));
+        components.push(format!("webgl.renderer={}", stringify_component(webgl.get("renderer"))));
+    }
+
+    components.push(format!("audio={}", stringify_component(fingerprint.get("audio"))));
+
+    if let Some(screen) = fingerprint.get("screen") {
+        for dim in ["width", "height", "colorDepth", "pixelDepth"] {
+            components.push(format!("screen.{}={}", dim, stringify_component(screen.get(dim))));
+        }
+    }
+
+    if let Some(plugins) = fingerprint.get("plugins").and_then(Value::as_array) {
+        let names: Vec<String> = plugins.iter().map(|p| stringify_component(Some(p))).collect();
+        components.push(format!("plugins={}", names.join(",")));
+    }
+
+    components
+}
+
+// Leitet eine deterministische `visitor_id` aus den stabilen Fingerprint-Komponenten ab - ein
+// 32-stelliger Hex-String aus dem 128-Bit-MurmurHash3-Digest.
+pub fn fingerprint_id(fingerprint: &Value) -> String {
+    let joined = stable_components(fingerprint).join("||");
+    let (h1, h2) = murmur3_x64_128(joined.as_bytes(), 0);
+    format!("{:016x}{:016x}", h1, h2)
+}
+
+// Navigator-/Screen-Eigenschaften, die sowohl auf der Top-Level-Seite als auch im
+// same-origin geladenen Iframe (siehe `fingerprint_iframe_handler`) gelesen werden - Evasion-Frameworks
+// patchen meist nur den Top-Level-`navigator`, nicht den des verschachtelten Browsing-Kontexts.
+const CONTEXT_COMPARISON_KEYS: &[&str] =
+    &["userAgent", "platform", "appVersion", "vendor", "productSub", "hardwareConcurrency", "timezone"];
+
+// Vergleicht die Top-Level-Werte mit denen aus dem Iframe und liefert `true`, sobald eine der
+// gemeinsamen Eigenschaften abweicht - ein starkes Indiz für eine nur partiell gepatchte
+// Browser-Umgebung. Liefert `false`, wenn keine Iframe-Antwort einging (z.B. Timeout).
+pub fn detect_context_mismatch(fingerprint: &Value) -> bool {
+    let iframe = match fingerprint.get("iframeFingerprint") {
+        Some(value) if value.is_object() => value,
+        _ => return false,
+    };
+
+    let scalar_mismatch = CONTEXT_COMPARISON_KEYS
+        .iter()
+        .any(|key| stringify_component(fingerprint.get(*key)) != stringify_component(iframe.get(*key)));
+
+    let screen_mismatch = match (fingerprint.get("screen"), iframe.get("screen")) {
+        (Some(top_screen), Some(iframe_screen)) => {
+            stringify_component(top_screen.get("width")) != stringify_component(iframe_screen.get("width"))
+                || stringify_component(top_screen.get("height")) != stringify_component(iframe_screen.get("height"))
+        }
+        _ => false,
+    };
+
+    scalar_mismatch || screen_mismatch
+}
+
+// Eigenschaften, die sowohl vom Main-Thread als auch im Worker-Bootstrap (siehe
+// `collectWorkerFingerprint()`) gelesen werden - `Object.defineProperty`-Overrides auf
+// `window.navigator` propagieren nicht in den frischen globalen Scope eines Workers, daher ist
+// jede Abweichung ein starkes Indiz für Laufzeit-Manipulation statt für echte Geräte-Vielfalt.
+const WORKER_COMPARISON_KEYS: &[&str] = &["userAgent", "platform", "hardwareConcurrency", "timezone"];
+
+// Vergleicht die Main-Thread-Werte mit den Worker-Readings und liefert die Menge der Felder,
+// die voneinander abweichen. Liefert eine leere Menge, wenn keine Worker-Antwort einging.
+pub fn detect_worker_divergence(fingerprint: &Value) -> Vec<String> {
+    let worker = match fingerprint.get("workerFingerprint") {
+        Some(value) if value.is_object() => value,
+        _ => return Vec::new(),
+    };
+
+    WORKER_COMPARISON_KEYS
+        .iter()
+        .filter(|key| stringify_component(fingerprint.get(**key)) != stringify_component(worker.get(**key)))
+        .map(|key| key.to_string())
+        .collect()
+}
+
+// Bots und privacy-orientierte Browser (Tor Browser, Firefox `resistFingerprinting`) normieren
+// oder lügen bei genau den Werten, die `collectFingerprint()` liest - ein "sauberer" Fingerprint
+// ist deshalb selbst ein Signal. Die folgenden Schwellwerte/Konstanten sind bekannte Tell-Tales
+// dieser Schutzmaßnahmen, keine kryptografisch exakten Signaturen.
+const RFP_AUDIO_CONSTANT: &str = "35.73833402246237";
+const RFP_CANVAS_CONSTANT_PREFIX: &str =
+    "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAMgAAAAyCAYAAAAZUZThAAAAAXNSR0IArs4c6QAAAA";
+const SPOOF_DEFAULT_HARDWARE_CONCURRENCY: &[&str] = &["2", "4"];
+const SPOOF_DEFAULT_DEVICE_MEMORY: &[&str] = &["2", "4"];
+const MOBILE_USER_AGENT_MARKERS: &[&str] = &["mobile", "android", "iphone", "ipad"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpoofingAssessment {
+    pub flags: Vec<String>,
+    pub score: u32,
+}
+
+// Klassifiziert einen Fingerprint anhand bekannter Anti-Fingerprinting-Tell-Tales. Jeder
+// zutreffende Check trägt einen Punkt zum Score bei, sodass die Falle automatisierte/
+// geschützte Clients anders behandeln kann als gewöhnliche Browser.
+pub fn classify_spoofing(fingerprint: &Value) -> SpoofingAssessment {
+    let mut flags = Vec::new();
+
+    if has_coarse_screen_grid(fingerprint) {
+        flags.push("coarse_screen_grid".to_string());
+    }
+    if has_utc_timezone_language_mismatch(fingerprint) {
+        flags.push("utc_timezone_language_mismatch".to_string());
+    }
+    if has_empty_plugins_on_desktop(fingerprint) {
+        flags.push("empty_plugins_desktop_ua".to_string());
+    }
+    if has_spoofed_hardware_defaults(fingerprint) {
+        flags.push("spoofed_hardware_defaults".to_string());
+    }
+    if has_rfp_constant_audio(fingerprint) {
+        flags.push("rfp_constant_audio".to_string());
+    }
+    if has_rfp_constant_canvas(fingerprint) {
+        flags.push("rfp_constant_canvas".to_string());
+    }
+    if fingerprint.get("webdriver").and_then(Value::as_bool).unwrap_or(false) {
+        flags.push("navigator_webdriver".to_string());
+    }
+    if has_zero_plugin_or_mimetype_counts(fingerprint) {
+        flags.push("zero_plugin_mimetype_counts".to_string());
+    }
+
+    let score = flags.len() as u32;
+    SpoofingAssessment { flags, score }
+}
+
+// Grobe Screen-Werte (Vielfache von 100) plus `innerWidth == outerWidth` deuten auf eine
+// normierte, nicht-reale Fensterumgebung hin (z.B. Headless-Chrome im Default-Viewport).
+fn has_coarse_screen_grid(fingerprint: &Value) -> bool {
+    let screen = match fingerprint.get("screen") {
+        Some(screen) => screen,
+        None => return false,
+    };
+    let width = screen.get("width").and_then(Value::as_i64);
+    let height = screen.get("height").and_then(Value::as_i64);
+    let inner_width = fingerprint.get("innerWidth").and_then(Value::as_i64);
+    let outer_width = fingerprint.get("outerWidth").and_then(Value::as_i64);
+
+    let is_coarse = |dimension: Option<i64>| dimension.map(|d| d % 100 == 0).unwrap_or(false);
+    let windows_match = matches!((inner_width, outer_width), (Some(i), Some(o)) if i == o);
+
+    is_coarse(width) && is_coarse(height) && windows_match
+}
+
+// Firefox mit `resistFingerprinting` meldet die Zeitzone pauschal als `UTC`, auch wenn die
+// gemeldete Sprache klar auf eine andere Region hindeutet.
+fn has_utc_timezone_language_mismatch(fingerprint: &Value) -> bool {
+    let timezone = fingerprint.get("timezone").and_then(Value::as_str).unwrap_or("");
+    let language = fingerprint.get("language").and_then(Value::as_str).unwrap_or("").to_lowercase();
+    timezone == "UTC" && !language.is_empty() && !language.starts_with("en")
+}
+
+fn has_empty_plugins_on_desktop(fingerprint: &Value) -> bool {
+    let user_agent = fingerprint.get("userAgent").and_then(Value::as_str).unwrap_or("").to_lowercase();
+    let is_desktop = !MOBILE_USER_AGENT_MARKERS.iter().any(|marker| user_agent.contains(marker));
+    let plugin_count = fingerprint.get("plugins").and_then(Value::as_array).map(Vec::len).unwrap_or(0);
+    is_desktop && plugin_count <= 1
+}
+
+fn has_spoofed_hardware_defaults(fingerprint: &Value) -> bool {
+    let hardware_concurrency = stringify_component(fingerprint.get("hardwareConcurrency"));
+    let device_memory = stringify_component(fingerprint.get("deviceMemory"));
+    SPOOF_DEFAULT_HARDWARE_CONCURRENCY.contains(&hardware_concurrency.as_str())
+        && SPOOF_DEFAULT_DEVICE_MEMORY.contains(&device_memory.as_str())
+}
+
+fn has_rfp_constant_audio(fingerprint: &Value) -> bool {
+    stringify_component(fingerprint.get("audio")) == RFP_AUDIO_CONSTANT
+}
+
+fn has_rfp_constant_canvas(fingerprint: &Value) -> bool {
+    stringify_component(fingerprint.get("canvas")).starts_with(RFP_CANVAS_CONSTANT_PREFIX)
+}
+
+fn has_zero_plugin_or_mimetype_counts(fingerprint: &Value) -> bool {
+    let plugin_count = fingerprint.get("plugins").and_then(Value::as_array).map(Vec::len);
+    let mime_type_count = fingerprint.get("mimeTypesLength").and_then(Value::as_i64);
+    plugin_count == Some(0) || mime_type_count == Some(0)
+}
+
+// MurmurHash3 (x64, 128-Bit-Variante) nach der Referenzimplementierung von Austin Appleby
+// (public domain) - keine passende Crate im Projekt vorhanden, daher von Hand portiert.
+fn murmur3_x64_128(data: &[u8], seed: u64) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    let mut h1: u64 = seed;
+    let mut h2: u64 = seed;
+
+    let len = data.len();
+    let n_blocks = len / 16;
+
+    for i in 0..n_blocks {
+        let block = &data[i * 16..i * 16 + 16];
+        let mut k1 = u64::from_le_bytes(block[0..8].try_into().unwrap());
+        let mut k2 = u64::from_le_bytes(block[8..16].try_into().unwrap());
+
+        k1 = k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 ^= k1;
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dce729);
+
+        k2 = k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 ^= k2;
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x38495ab5);
+    }
+
+    let tail = &data[n_blocks * 16..];
+    let tail_len = tail.len();
+    let mut k1: u64 = 0;
+    let mut k2: u64 = 0;
+
+    if tail_len > 8 {
+        for p in (8..tail_len).rev() {
+            k2 ^= (tail[p] as u64) << ((p - 8) * 8);
+        }
+        k2 = k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 ^= k2;
+    }
+    if tail_len > 0 {
+        let first_len = tail_len.min(8);
+        for p in (0..first_len).rev() {
+            k1 ^= (tail[p] as u64) << (p * 8);
+        }
+        k1 = k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 ^= k1;
+    }
+
+    h1 ^= len as u64;
+    h2 ^= len as u64;
+
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+
+    (h1, h2)
+}
+
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^= k >> 33;
+    k
+}
@@ -0,0 +1,331 @@
+// backend/rust_honeypots/src/geoip.rs
+//
+// `lookup_geoip` rief bisher für jedes Event synchron ip-api.com auf - das scheitert, sobald
+// TRAPD air-gapped läuft, und die kostenlose Quote (~45 req/min) ist unter einem Scan-Burst
+// binnen Sekunden aufgebraucht. Dieses Modul löst Standort-/ISP-Daten stattdessen bevorzugt aus
+// einer lokal gemountenen MaxMind-GeoLite2-Datenbank (`geoip2::City`, optional `geoip2::Isp`)
+// auf - ein In-Memory-Read im Mikrosekundenbereich statt eines Netzwerk-Roundtrips - und greift
+// nur dann auf den HTTP-Lookup zurück, wenn keine DB konfiguriert ist oder kein Treffer vorliegt.
+
+use std::net::IpAddr;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use lru::LruCache;
+use maxminddb::geoip2;
+use reqwest::Client;
+use tokio::sync::Mutex;
+
+use crate::common::{GeoLocation, SharedAppState};
+
+// Macht das HTTP-Backend austauschbar - ip-api.com ist kostenlos, aber streng ratelimitiert;
+// `IpGeolocationIoProvider` erlaubt Betreibern, stattdessen einen authentifizierten,
+// höherratigen Dienst einzustecken, ohne `resolve_geoip` anzufassen. Eine künftige lokale-DB-
+// Implementierung (siehe `lookup_from_mmdb`) ließe sich über dieselbe Schnittstelle einhängen.
+#[async_trait]
+pub trait GeoIpProvider: Send + Sync {
+    async fn resolve(&self, ip: IpAddr) -> GeoLocation;
+}
+
+// Bisheriger Pfad: das kostenlose ip-api.com, ohne API-Key.
+pub struct IpApiProvider {
+    http_client: Client,
+}
+
+impl IpApiProvider {
+    pub fn new(http_client: Client) -> Self {
+        Self { http_client }
+    }
+}
+
+#[async_trait]
+impl GeoIpProvider for IpApiProvider {
+    async fn resolve(&self, ip: IpAddr) -> GeoLocation {
+        lookup_geoip_http(ip, &self.http_client).await
+    }
+}
+
+// Authentifizierter Anbieter für Betreiber, die die ip-api.com-Freigrenze (~45 req/min)
+// gegen eine höhere, bezahlte Quote eintauschen wollen.
+pub struct IpGeolocationIoProvider {
+    http_client: Client,
+    api_key: String,
+}
+
+impl IpGeolocationIoProvider {
+    pub fn new(http_client: Client, api_key: String) -> Self {
+        Self { http_client, api_key }
+    }
+}
+
+#[async_trait]
+impl GeoIpProvider for IpGeolocationIoProvider {
+    async fn resolve(&self, ip: IpAddr) -> GeoLocation {
+        let url = format!(
+            "https://api.ipgeolocation.io/ipgeo?apiKey={}&ip={}&fields=geo,isp",
+            self.api_key, ip
+        );
+
+        match self.http_client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<serde_json::Value>().await {
+                    Ok(geo_data) => {
+                        let location = GeoLocation {
+                            country_code: geo_data.get("country_code2").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            country_name: geo_data.get("country_name").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            region_code: geo_data.get("state_prov").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            region_name: geo_data.get("state_prov").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            city: geo_data.get("city").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            latitude: geo_data.get("latitude").and_then(|v| v.as_str()).and_then(|s| s.parse().ok()),
+                            longitude: geo_data.get("longitude").and_then(|v| v.as_str()).and_then(|s| s.parse().ok()),
+                            timezone: geo_data.get("time_zone").and_then(|v| v.get("name")).and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            isp: geo_data.get("isp").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            organization: geo_data.get("organization").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        };
+                        println!("GeoIP lookup (ipgeolocation.io) erfolgreich: {:?}", location);
+                        location
+                    }
+                    Err(e) => {
+                        eprintln!("ipgeolocation.io: Antwort für {} konnte nicht geparst werden: {:?}", ip, e);
+                        GeoLocation::default()
+                    }
+                }
+            }
+            Ok(response) => {
+                eprintln!("ipgeolocation.io: Anfrage für {} fehlgeschlagen mit Status {}", ip, response.status());
+                GeoLocation::default()
+            }
+            Err(e) => {
+                eprintln!("ipgeolocation.io: Anfrage für {} fehlgeschlagen: {:?}", ip, e);
+                GeoLocation::default()
+            }
+        }
+    }
+}
+
+// Wählt den GeoIP-Provider anhand der Umgebung: mit `IPGEOLOCATION_IO_API_KEY` der
+// authentifizierte Dienst, sonst weiterhin das kostenlose ip-api.com.
+pub fn build_provider(http_client: Client) -> std::sync::Arc<dyn GeoIpProvider> {
+    match std::env::var("IPGEOLOCATION_IO_API_KEY") {
+        Ok(api_key) if !api_key.is_empty() => {
+            println!("GeoIP: nutze ipgeolocation.io als Provider");
+            std::sync::Arc::new(IpGeolocationIoProvider::new(http_client, api_key))
+        }
+        _ => std::sync::Arc::new(IpApiProvider::new(http_client)),
+    }
+}
+
+// Anzahl der zuletzt gesehenen IPs, für die eine GeoIP-Antwort vorgehalten wird, sowie wie
+// lange ein Eintrag gültig bleibt, bevor er als veraltet gilt und neu aufgelöst wird.
+pub const GEOIP_CACHE_CAPACITY: usize = 10_000;
+pub const GEOIP_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub type GeoIpCache = Mutex<LruCache<IpAddr, (GeoLocation, Instant)>>;
+
+// Baut einen leeren, auf `GEOIP_CACHE_CAPACITY` begrenzten Cache - wird einmal in `AppState`
+// angelegt und über alle Ingest-Pfade geteilt.
+pub fn new_cache() -> GeoIpCache {
+    Mutex::new(LruCache::new(NonZeroUsize::new(GEOIP_CACHE_CAPACITY).unwrap()))
+}
+
+// ip-api.com sperrt Clients ab ~45 Anfragen/Minute - ohne Drossel bringt ein Scan-Burst mit
+// vielen neuen Quell-IPs die Egress-IP des Honeypots mitten im Vorfall um die Sperre.
+pub const GEOIP_RATE_LIMIT_PER_MINUTE: u32 = 45;
+
+// Ab dieser Wartezeit wird der Lookup lieber übersprungen (Default-Location), statt den
+// Ingest-Request auf einen freien Permit warten zu lassen - der Cache fängt wiederholte
+// Anfragen für dieselbe IP ohnehin schon ab.
+const GEOIP_RATE_LIMIT_MAX_WAIT: Duration = Duration::from_millis(250);
+
+pub type GeoIpRateLimiter = DefaultDirectRateLimiter;
+
+// Baut den Rate-Limiter für ausgehende GeoIP-Anfragen - einmal in `AppState` angelegt und über
+// alle Ingest-Pfade geteilt, damit die Quote prozessweit statt pro Request gilt.
+pub fn build_rate_limiter() -> GeoIpRateLimiter {
+    let quota = Quota::per_minute(NonZeroU32::new(GEOIP_RATE_LIMIT_PER_MINUTE).unwrap());
+    RateLimiter::direct(quota)
+}
+
+// Öffnet die konfigurierte `.mmdb`-Datei einmalig beim Start und hält sie memory-mapped im
+// Speicher - der Reader ist `Send + Sync` und wird als `Arc` über `AppState` geteilt.
+pub fn open_reader(path: &str) -> Option<maxminddb::Reader<Vec<u8>>> {
+    match maxminddb::Reader::open_readfile(path) {
+        Ok(reader) => {
+            println!("GeoIP: lokale Datenbank geladen ({})", path);
+            Some(reader)
+        }
+        Err(e) => {
+            eprintln!("GeoIP: konnte Datenbank '{}' nicht öffnen ({:?}), falle auf HTTP-Lookup zurück", path, e);
+            None
+        }
+    }
+}
+
+// Löst die GeoIP-Daten einer IP auf, mit einem LRU+TTL-Cache vor dem eigentlichen Resolver davor:
+// unter einem Scan-Burst fragen dieselben Quell-IPs tausendfach an, ohne dass jede davon erneut
+// gegen die lokale DB oder ip-api.com aufgelöst werden müsste.
+pub async fn lookup_geoip(ip: IpAddr, state: &SharedAppState) -> GeoLocation {
+    {
+        let mut cache = state.geoip_cache.lock().await;
+        if let Some((location, cached_at)) = cache.get(&ip) {
+            if cached_at.elapsed() < GEOIP_CACHE_TTL {
+                return location.clone();
+            }
+            cache.pop(&ip);
+        }
+    }
+
+    let location = resolve_geoip(ip, state).await;
+
+    let mut cache = state.geoip_cache.lock().await;
+    cache.put(ip, (location.clone(), Instant::now()));
+    location
+}
+
+// Löst die GeoIP-Daten einer IP auf: bevorzugt aus der lokalen MaxMind-Datenbank, nur bei
+// fehlender DB oder fehlendem Treffer per HTTP-Lookup über den konfigurierten `geoip_provider`
+// (ip-api.com oder ipgeolocation.io, siehe `build_provider`). Private/Loopback-/
+// Multicast-Adressen werden wie bisher ohne jeden Lookup übersprungen.
+async fn resolve_geoip(ip: IpAddr, state: &SharedAppState) -> GeoLocation {
+    let is_private = match ip {
+        IpAddr::V4(ipv4) => ipv4.is_private() || ipv4.is_loopback() || ipv4.is_multicast(),
+        IpAddr::V6(ipv6) => ipv6.is_loopback() || ipv6.is_multicast() || ipv6.is_unspecified(),
+    };
+
+    if is_private {
+        println!("Skipping GeoIP lookup for private/local IP: {}", ip);
+        return GeoLocation::default();
+    }
+
+    if let Some(reader) = &state.geoip_reader {
+        if let Some(location) = lookup_from_mmdb(reader, &state.geoip_isp_reader, ip) {
+            println!("GeoIP lookup (lokale DB) erfolgreich: {:?}", location);
+            return location;
+        }
+    }
+
+    // Nur der ausgehende HTTP-Lookup braucht die Drossel - der mmdb-Pfad oben ist ein lokaler
+    // Read und verbraucht keine Provider-Quote.
+    match state.geoip_rate_limiter.check() {
+        Ok(_) => state.geoip_provider.resolve(ip).await,
+        Err(not_until) => {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            if wait > GEOIP_RATE_LIMIT_MAX_WAIT {
+                println!("GeoIP: Rate-Limit erschöpft (Wartezeit {:?}), überspringe Lookup für {}", wait, ip);
+                GeoLocation::default()
+            } else {
+                tokio::time::sleep(wait).await;
+                // Nach dem Warten erneut einen Permit holen - sonst würden alle gleichzeitig
+                // wartenden Lookups ohne Permit durchgehen und die Quote überschreiten.
+                if state.geoip_rate_limiter.check().is_ok() {
+                    state.geoip_provider.resolve(ip).await
+                } else {
+                    GeoLocation::default()
+                }
+            }
+        }
+    }
+}
+
+// Liest `country`/`subdivisions`/`city`/`location` aus der City-DB sowie, falls konfiguriert,
+// `isp`/`organization` aus einer separaten ISP-DB. Liefert `None`, wenn die City-DB keinen
+// Treffer für die IP hat, damit der Aufrufer auf den HTTP-Lookup zurückfallen kann.
+fn lookup_from_mmdb(
+    city_reader: &maxminddb::Reader<Vec<u8>>,
+    isp_reader: &Option<std::sync::Arc<maxminddb::Reader<Vec<u8>>>>,
+    ip: IpAddr,
+) -> Option<GeoLocation> {
+    let city: geoip2::City = city_reader.lookup(ip).ok()??;
+
+    let country_code = city.country.as_ref().and_then(|c| c.iso_code).map(str::to_string);
+    let country_name = city
+        .country
+        .as_ref()
+        .and_then(|c| c.names.as_ref())
+        .and_then(|names| names.get("en"))
+        .map(|s| s.to_string());
+
+    let subdivision = city.subdivisions.as_ref().and_then(|subs| subs.first());
+    let region_code = subdivision.and_then(|s| s.iso_code).map(str::to_string);
+    let region_name = subdivision
+        .and_then(|s| s.names.as_ref())
+        .and_then(|names| names.get("en"))
+        .map(|s| s.to_string());
+
+    let city_name = city
+        .city
+        .as_ref()
+        .and_then(|c| c.names.as_ref())
+        .and_then(|names| names.get("en"))
+        .map(|s| s.to_string());
+
+    let latitude = city.location.as_ref().and_then(|l| l.latitude);
+    let longitude = city.location.as_ref().and_then(|l| l.longitude);
+    let timezone = city.location.as_ref().and_then(|l| l.time_zone).map(str::to_string);
+
+    let (isp, organization) = isp_reader
+        .as_ref()
+        .and_then(|reader| reader.lookup::<geoip2::Isp>(ip).ok().flatten())
+        .map(|record| (record.isp.map(str::to_string), record.organization.map(str::to_string)))
+        .unwrap_or((None, None));
+
+    Some(GeoLocation {
+        country_code,
+        country_name,
+        region_code,
+        region_name,
+        city: city_name,
+        latitude,
+        longitude,
+        timezone,
+        isp,
+        organization,
+    })
+}
+
+// GeoIP lookup using ip-api.com (free service) - Fallback, wenn keine lokale DB konfiguriert ist
+// oder diese keinen Treffer für die IP hatte.
+async fn lookup_geoip_http(ip: IpAddr, http_client: &Client) -> GeoLocation {
+    let url = format!("http://ip-api.com/json/{}?fields=status,message,country,countryCode,region,regionName,city,lat,lon,timezone,isp,org", ip);
+
+    println!("Looking up GeoIP for: {}", ip);
+
+    match http_client.get(&url).send().await {
+        Ok(response) => {
+            if response.status().is_success() {
+                if let Ok(geo_data) = response.json::<serde_json::Value>().await {
+                    if geo_data.get("status").and_then(|s| s.as_str()) == Some("success") {
+                        let location = GeoLocation {
+                            country_code: geo_data.get("countryCode").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            country_name: geo_data.get("country").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            region_code: geo_data.get("region").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            region_name: geo_data.get("regionName").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            city: geo_data.get("city").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            latitude: geo_data.get("lat").and_then(|v| v.as_f64()),
+                            longitude: geo_data.get("lon").and_then(|v| v.as_f64()),
+                            timezone: geo_data.get("timezone").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            isp: geo_data.get("isp").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            organization: geo_data.get("org").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                        };
+                        println!("GeoIP lookup successful: {:?}", location);
+                        return location;
+                    } else {
+                        println!("GeoIP API returned error status for {}: {:?}", ip, geo_data);
+                    }
+                } else {
+                    println!("Failed to parse GeoIP response as JSON for {}", ip);
+                }
+            } else {
+                println!("GeoIP API request failed with status: {} for {}", response.status(), ip);
+            }
+        }
+        Err(e) => {
+            eprintln!("GeoIP lookup failed for {}: {:?}", ip, e);
+        }
+    }
+
+    GeoLocation::default()
+}
@@ -0,0 +1,83 @@
+// backend/rust_honeypots/src/http_client.rs
+//
+// Der ausgehende HTTP-Client (Supabase, Python-KI) lief bislang über `Client::new()`
+// ohne Timeout oder Retries - eine langsame/unerreichbare Gegenstelle konnte einen
+// Honeypot-Connection-Handler also unbegrenzt blockieren.
+
+use std::env;
+use std::time::Duration;
+
+use reqwest::{Client, ClientBuilder};
+
+// Konfiguration für den geteilten `reqwest::Client`.
+#[derive(Debug, Clone)]
+pub struct HttpClientOptions {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub backoff_base: Duration,
+}
+
+impl Default for HttpClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(120),
+            max_retries: 3,
+            backoff_base: Duration::from_millis(200),
+        }
+    }
+}
+
+impl HttpClientOptions {
+    // Liest Timeout/Retries/Backoff aus der Umgebung, damit ein Operator sie ohne Neubau
+    // konfigurieren kann - ungesetzte oder unparsbare Werte fallen auf `Default` zurück.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            timeout: env::var("HTTP_CLIENT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            max_retries: env::var("HTTP_CLIENT_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(defaults.max_retries),
+            backoff_base: env::var("HTTP_CLIENT_BACKOFF_BASE_MS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.backoff_base),
+        }
+    }
+
+    pub fn build(&self) -> Client {
+        ClientBuilder::new().timeout(self.timeout).build().unwrap_or_else(|e| {
+            eprintln!("Konnte HTTP-Client nicht mit Optionen bauen ({:?}), nutze Default", e);
+            Client::new()
+        })
+    }
+}
+
+// Führt `send_request` mit begrenzten Retries und exponentiellem Backoff aus. Nur
+// Transport-Fehler und 5xx-Antworten gelten als vorübergehend und werden wiederholt.
+pub async fn send_with_retry<F, Fut>(opts: &HttpClientOptions, mut send_request: F) -> reqwest::Result<reqwest::Response>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    let mut attempt = 0;
+    loop {
+        match send_request().await {
+            Ok(res) if res.status().is_server_error() && attempt < opts.max_retries => {
+                attempt += 1;
+                tokio::time::sleep(opts.backoff_base * 2u32.pow(attempt)).await;
+            }
+            Ok(res) => return Ok(res),
+            Err(e) if attempt < opts.max_retries && (e.is_timeout() || e.is_connect()) => {
+                attempt += 1;
+                tokio::time::sleep(opts.backoff_base * 2u32.pow(attempt)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
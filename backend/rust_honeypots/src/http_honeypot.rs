@@ -2,7 +2,7 @@
 
 use axum::{
     extract::{ConnectInfo, State, OriginalUri},
-    response::{Html, IntoResponse},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
     Router,
 };
@@ -18,17 +18,38 @@ use once_cell::sync::Lazy;
 use sha2::{Sha256, Digest};
 use base64::{Engine as _, engine::general_purpose};
 
-use crate::common::{SharedAppState, lookup_geoip};
+use crate::auth_decoy;
+use crate::common::SharedAppState;
+use crate::decoy::DecoyProfile;
+use crate::fingerprint_analysis;
+use crate::geoip::lookup_geoip;
+use crate::http_client::send_with_retry;
+use crate::identity_graph;
+use crate::log_sink::AttackerLog;
+use crate::mime_decoy;
+use crate::observability;
+use crate::openapi_decoy::ApiSurface;
+use crate::session_tracker;
+
+// Einmal pro Prozess geladene Fake-API-Surface (siehe `openapi_decoy`), aus der zusätzliche
+// Routen synthetisiert werden.
+static API_SURFACE: Lazy<ApiSurface> = Lazy::new(ApiSurface::load);
 
 // Öffentlicher Router, damit er von main.rs eingebunden werden kann
 pub fn create_http_router(app_state: SharedAppState) -> Router {
-    Router::new()
+    let router = Router::new()
         .route("/", get(honeypot_handler))
         .route("/*path", get(honeypot_handler))
         .route("/", post(honeypot_handler_post))
         .route("/*path", post(honeypot_handler_post))
         .route("/fingerprint", post(fingerprint_handler))
-        .with_state(app_state)
+        .route("/fingerprint/iframe", get(fingerprint_iframe_handler));
+
+    // Registriert die aus der OpenAPI-Spec synthetisierten Routen, bevor der State-Typ
+    // durch `with_state` auf `()` festgelegt wird.
+    let router = API_SURFACE.register_routes(router);
+
+    router.with_state(app_state)
 }
 
 // Handler für GET-Anfragen (ohne Body-Extraction)
@@ -41,16 +62,37 @@ async fn honeypot_handler(
     http_version: Version,
 ) -> impl IntoResponse {
     // Filter für Browser-spezifische Anfragen (Favicon, etc.)
-    let request_path = uri.path();
-    if should_ignore_request(request_path, &headers) {
+    let request_path = uri.path().to_string();
+    if should_ignore_request(&request_path, &headers) {
         println!("Ignoriere Browser-Anfrage: {}", request_path);
-        return Html(generate_simple_404().await);
+        let profile = DecoyProfile::select(None, &request_path);
+        return render_decoy_response(profile, profile.render_404());
     }
-
-    let (disinformation_content, _) = log_http_interaction(method, addr, state, uri, headers, http_version, None).await;
-    
-    // Dynamische HTML-Antwort generieren
-    Html(generate_dynamic_html_response(disinformation_content).await)
+    // Identitäts-Token vor dem Move von `headers` in `log_http_interaction` auflösen (siehe
+    // `identity_graph`): wiederverwendet ein plausibles Cookie, sonst Erstvergabe.
+    let identity_token = identity_graph::resolve_identity_token(&headers, &addr.ip().to_string());
+
+    // Canary-Token (siehe `mime_decoy`) vor dem Logging erzeugen, damit es mit in
+    // `interaction_data` landet - sonst lässt sich eine spätere Verwendung des vermeintlich
+    // erbeuteten Secrets nie auf diese Anfrage zurückführen.
+    let mime_decoy = mime_decoy::DecoyKind::from_path(&request_path)
+        .map(|kind| (kind, mime_decoy::canary_token(&addr.ip().to_string(), &request_path)));
+    let canary_token = mime_decoy.as_ref().map(|(_, token)| token.clone());
+
+    let (disinformation_content, _, scanner_type, auth_challenge, session_cookie) =
+        log_http_interaction(method, addr, state, uri, headers, http_version, None, canary_token).await;
+
+    let mut response = if let Some(challenge) = auth_challenge {
+        auth_decoy::simulate_auth_delay().await;
+        auth_decoy::challenge_response(&request_path, challenge.attempt.is_some()).into_response()
+    } else if let Some((kind, token)) = mime_decoy {
+        render_mime_decoy_response(kind, &token)
+    } else {
+        let profile = DecoyProfile::select(scanner_type.as_deref(), &request_path);
+        render_decoy_response(profile, generate_dynamic_html_response(profile, disinformation_content, &identity_token).await)
+    };
+    apply_session_cookie(&mut response, session_cookie);
+    response
 }
 
 // Handler für POST-Anfragen (mit Body-Extraction)
@@ -64,16 +106,72 @@ async fn honeypot_handler_post(
     body: String, // Extrahiere den Request Body als String
 ) -> impl IntoResponse {
     // Filter für Browser-spezifische Anfragen
-    let request_path = uri.path();
-    if should_ignore_request(request_path, &headers) {
+    let request_path = uri.path().to_string();
+    if should_ignore_request(&request_path, &headers) {
         println!("Ignoriere Browser-POST-Anfrage: {}", request_path);
-        return Html(generate_simple_404().await);
+        let profile = DecoyProfile::select(None, &request_path);
+        return render_decoy_response(profile, profile.render_404());
     }
+    // Identitäts-Token vor dem Move von `headers` in `log_http_interaction` auflösen (siehe
+    // `identity_graph`): wiederverwendet ein plausibles Cookie, sonst Erstvergabe.
+    let identity_token = identity_graph::resolve_identity_token(&headers, &addr.ip().to_string());
+
+    // Canary-Token (siehe `mime_decoy`) vor dem Logging erzeugen, damit es mit in
+    // `interaction_data` landet - sonst lässt sich eine spätere Verwendung des vermeintlich
+    // erbeuteten Secrets nie auf diese Anfrage zurückführen. Verrät der Pfad keine Dateiendung
+    // (z.B. ein Upload auf `/upload`), werden stattdessen die führenden Magic Bytes des Bodys
+    // gesnifft.
+    let decoy_kind = mime_decoy::DecoyKind::from_path(&request_path)
+        .or_else(|| mime_decoy::DecoyKind::from_body(body.as_bytes()));
+    let mime_decoy = decoy_kind.map(|kind| (kind, mime_decoy::canary_token(&addr.ip().to_string(), &request_path)));
+    let canary_token = mime_decoy.as_ref().map(|(_, token)| token.clone());
+
+    let (disinformation_content, _, scanner_type, auth_challenge, session_cookie) =
+        log_http_interaction(method, addr, state, uri, headers, http_version, Some(body), canary_token).await;
+
+    let mut response = if let Some(challenge) = auth_challenge {
+        auth_decoy::simulate_auth_delay().await;
+        auth_decoy::challenge_response(&request_path, challenge.attempt.is_some()).into_response()
+    } else if let Some((kind, token)) = mime_decoy {
+        render_mime_decoy_response(kind, &token)
+    } else {
+        let profile = DecoyProfile::select(scanner_type.as_deref(), &request_path);
+        render_decoy_response(profile, generate_dynamic_html_response(profile, disinformation_content, &identity_token).await)
+    };
+    apply_session_cookie(&mut response, session_cookie);
+    response
+}
 
-    let (disinformation_content, _) = log_http_interaction(method, addr, state, uri, headers, http_version, Some(body)).await;
-    
-    // Dynamische HTML-Antwort generieren
-    Html(generate_dynamic_html_response(disinformation_content).await)
+// Baut die Antwort für ein gewähltes `DecoyProfile`: passende Header plus gerendertes Markup
+fn render_decoy_response(profile: DecoyProfile, body: String) -> Response {
+    let mut headers = HeaderMap::new();
+    profile.apply_headers(&mut headers);
+    (headers, Html(body)).into_response()
+}
+
+// Setzt das `Set-Cookie`-Header einer neu eröffneten Session (siehe `session_tracker`) auf der
+// ausgehenden Antwort, sofern eine vergeben wurde.
+fn apply_session_cookie(response: &mut Response, session_cookie: Option<axum::http::HeaderValue>) {
+    if let Some(cookie) = session_cookie {
+        response.headers_mut().insert(axum::http::header::SET_COOKIE, cookie);
+    }
+}
+
+// Baut die Antwort für ein erkanntes "interessantes" Dateiformat (siehe `mime_decoy`): passender
+// Content-Type und Body mit eingebettetem Canary-Token statt der generischen HTML-Decoy-Seite.
+// Der Token wird vom Aufrufer übergeben, statt hier neu erzeugt zu werden, damit derselbe Wert
+// bereits zuvor in `interaction_data` geloggt wurde und eine spätere Verwendung des
+// vermeintlich erbeuteten Secrets auf diese Anfrage zurückgeführt werden kann.
+fn render_mime_decoy_response(kind: mime_decoy::DecoyKind, token: &str) -> Response {
+    let body = kind.render(token);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::CONTENT_TYPE,
+        axum::http::HeaderValue::from_static(kind.content_type()),
+    );
+
+    (headers, body).into_response()
 }
 
 // Handler für JavaScript-Fingerprinting-Daten
@@ -89,10 +187,40 @@ async fn fingerprint_handler(
     
     // Parse fingerprint data
     if let Ok(fingerprint_data) = serde_json::from_str::<Value>(&body) {
+        // Stabile Visitor-ID aus den Fingerprint-Komponenten ableiten (siehe
+        // `fingerprint_analysis`), damit Wiederbesuche desselben Geräts erkennbar werden.
+        let visitor_id = fingerprint_analysis::fingerprint_id(&fingerprint_data);
+        let spoofing = fingerprint_analysis::classify_spoofing(&fingerprint_data);
+
+        // Identitäts-Graph (siehe `identity_graph`): verschmilzt Cookie-, localStorage- und
+        // sessionStorage-Token mit der Fingerprint-ID zu einer Identität, die auch eine
+        // Cookie-Löschung oder einen gedrifteten Fingerprint übersteht.
+        let identity_signals = fingerprint_data.get("identitySignals");
+        let cookie_signal = identity_signals.and_then(|s| s.get("cookieToken")).and_then(Value::as_str).unwrap_or("");
+        let local_signal = identity_signals.and_then(|s| s.get("localStorageToken")).and_then(Value::as_str).unwrap_or("");
+        let session_signal = identity_signals.and_then(|s| s.get("sessionStorageToken")).and_then(Value::as_str).unwrap_or("");
+        let identity_id = identity_graph::merge_identity(&[cookie_signal, local_signal, session_signal, &visitor_id]);
+
+        // Vergleich der Top-Level- gegen die Iframe-Fingerprint-Lesungen (siehe
+        // `fingerprint_iframe_handler`) - Evasion-Frameworks patchen oft nur den Top-Level-
+        // `navigator`, nicht den des verschachtelten Browsing-Kontexts.
+        let context_mismatch = fingerprint_analysis::detect_context_mismatch(&fingerprint_data);
+
+        // Worker-Gegenprobe (siehe `collectWorkerFingerprint()`): Overrides auf `navigator`, die
+        // nur im Main-Thread gepatcht wurden, tauchen hier als Divergenz auf und verraten damit
+        // das eingesetzte Evasion-Toolkit, nicht nur das Gerät selbst.
+        let worker_divergence = fingerprint_analysis::detect_worker_divergence(&fingerprint_data);
+
         let mut enhanced_fingerprint = json!({
             "source_ip": client_ip,
             "honeypot_type": "http_fingerprint",
             "timestamp": chrono::Utc::now().to_rfc3339(),
+            "visitor_id": visitor_id,
+            "identity_id": identity_id,
+            "spoofing_flags": spoofing.flags,
+            "spoofing_score": spoofing.score,
+            "context_mismatch": context_mismatch,
+            "worker_divergence": worker_divergence,
             "fingerprint_data": fingerprint_data,
             "headers": headers.iter().map(|(k, v)| {
                 (k.to_string(), Value::String(v.to_str().unwrap_or("").to_string()))
@@ -100,7 +228,7 @@ async fn fingerprint_handler(
         });
 
         // Add GeoIP data
-        let geo_location = lookup_geoip(addr.ip(), &state.http_client).await;
+        let geo_location = lookup_geoip(addr.ip(), &state).await;
         if let Some(country_code) = &geo_location.country_code {
             enhanced_fingerprint["country_code"] = json!(country_code);
         }
@@ -108,33 +236,19 @@ async fn fingerprint_handler(
             enhanced_fingerprint["country_name"] = json!(country_name);
         }
 
-        // Log to Supabase (mit Fallback falls Tabelle nicht existiert)
-        let supabase_table_url = format!("{}/rest/v1/browser_fingerprints", state.supabase_api_url);
-        match state.http_client
-            .post(&supabase_table_url)
-            .header("apikey", &state.supabase_service_role_key)
-            .header("Authorization", format!("Bearer {}", &state.supabase_service_role_key))
-            .header("Content-Type", "application/json")
-            .json(&enhanced_fingerprint)
-            .send()
-            .await
-        {
-            Ok(res) => {
-                if res.status().is_success() {
-                    println!("✅ Browser fingerprint successfully logged to Supabase");
-                } else if res.status() == 404 {
-                    eprintln!("⚠️  browser_fingerprints table not found. Run Supabase migrations first:");
-                    eprintln!("   cd backend/supabase && supabase db push");
-                    eprintln!("   Or create the table manually in your Supabase dashboard");
-                } else {
-                    eprintln!("❌ Failed to log browser fingerprint: {}", res.status());
-                    if let Ok(body) = res.text().await {
-                        eprintln!("Response: {}", body);
-                    }
-                }
-            },
-            Err(e) => {
-                eprintln!("❌ Error logging browser fingerprint: {:?}", e);
+        // An alle konfigurierten LogSinks weiterleiten (Supabase, Datei, ...), statt hart
+        // gegen die `browser_fingerprints`-Tabelle zu posten.
+        let attacker_log = AttackerLog {
+            source_ip: client_ip.clone(),
+            honeypot_type: "http_fingerprint".to_string(),
+            interaction_data: enhanced_fingerprint,
+            status: "logged".to_string(),
+        };
+
+        for sink in &state.log_sinks {
+            if let Err(e) = sink.write(&attacker_log).await {
+                eprintln!("❌ Fehler beim Schreiben des Fingerprints in einen Sink: {:?}", e);
+                observability::record_sink_failure("http_fingerprint");
             }
         }
     }
@@ -143,8 +257,40 @@ async fn fingerprint_handler(
     "OK"
 }
 
-// Allgemeine Funktion zum Loggen und Weiterleiten von HTTP-Interaktionen
-async fn log_http_interaction(
+// Liefert die Iframe-Seite, die die Hauptseite per `collectIframeFingerprint()` einbettet (vom
+// selben Host und damit same-origin, nicht von einer fremden Origin): Evasion-Frameworks patchen häufig nur den `navigator` des Top-Level-Dokuments und
+// übersehen den eines verschachtelten Browsing-Kontexts. Die Seite liest dieselben
+// Navigator-/Screen-Eigenschaften erneut aus und schickt sie per `postMessage` an das
+// Eltern-Fenster zurück, das sie beim nächsten `/fingerprint`-POST mitschickt.
+async fn fingerprint_iframe_handler() -> impl IntoResponse {
+    Html(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"></head><body>
+<script>
+(function() {
+    var data = {
+        userAgent: navigator.userAgent,
+        platform: navigator.platform,
+        appVersion: navigator.appVersion,
+        vendor: navigator.vendor,
+        productSub: navigator.productSub,
+        hardwareConcurrency: navigator.hardwareConcurrency,
+        timezone: Intl.DateTimeFormat().resolvedOptions().timeZone,
+        screen: { width: screen.width, height: screen.height }
+    };
+    if (window.parent) {
+        window.parent.postMessage({ type: 'trapd_iframe_fingerprint', data: data }, '*');
+    }
+})();
+</script>
+</body></html>"#,
+    )
+}
+
+// Allgemeine Funktion zum Loggen und Weiterleiten von HTTP-Interaktionen. `pub(crate)`,
+// damit auch `openapi_decoy` Treffer gegen die synthetisierte Fake-API über dieselbe
+// Pipeline protokolliert.
+pub(crate) async fn log_http_interaction(
     method: Method,
     addr: SocketAddr,
     state: SharedAppState,
@@ -152,8 +298,10 @@ async fn log_http_interaction(
     headers: HeaderMap,
     http_version_raw: Version,
     request_body: Option<String>,
-) -> (String, Value) {
+    canary_token: Option<String>,
+) -> (String, Value, Option<String>, Option<auth_decoy::AuthChallenge>, Option<axum::http::HeaderValue>) {
     let client_ip = addr.ip().to_string();
+    observability::record_interaction("http", &client_ip);
     let client_port = addr.port();
     let full_uri = uri.to_string();
     let request_path = uri.path();
@@ -171,7 +319,7 @@ async fn log_http_interaction(
     // }
 
     // GeoIP lookup
-    let geo_location = lookup_geoip(addr.ip(), &state.http_client).await;
+    let geo_location = lookup_geoip(addr.ip(), &state).await;
     println!("GeoIP for {}: {:?}", client_ip, geo_location);
 
     let http_version_str = format!("{:?}", http_version_raw);
@@ -215,6 +363,16 @@ async fn log_http_interaction(
         }
     }
 
+    // Auth-Challenge für geschützte Pfade (siehe `auth_decoy`) - muss vor dem Move von
+    // `parsed_body_data` in `interaction_data` laufen, da Formular-Credentials daraus
+    // extrahiert werden.
+    let auth_challenge = auth_decoy::evaluate(request_path, &client_ip, &headers, parsed_body_data.as_ref());
+
+    // Session-Tracking (siehe `session_tracker`): trägt diese Anfrage in die Angriffskette der
+    // Quell-IP ein und liefert eine neue Session-ID zurück, sobald es der Erstkontakt war.
+    let (session_id, is_new_session) =
+        session_tracker::record_request(&client_ip, http_method, request_path, request_body.as_deref());
+
     println!("Honeypot-Interaktion: IP: {}, Port: {}, Methode: {}, Pfad: {}, Version: {}, User-Agent: {}",
              client_ip, client_port, http_method, request_path, http_version_str, user_agent);
     if let Some(body) = &request_body {
@@ -273,106 +431,70 @@ async fn log_http_interaction(
     });
 
 
-    let mut supabase_log_payload = json!({
-        "source_ip": client_ip,
-        "honeypot_type": "http",
-        "interaction_data": interaction_data,
-        "status": "logged",
-        // Advanced Fingerprinting Felder
-        "scanner_type": fingerprint_result.scanner_type,
-        "tool_confidence": fingerprint_result.tool_confidence,
-        "threat_level": format!("{:?}", fingerprint_result.threat_level),
-        "is_real_browser": fingerprint_result.browser_fingerprint.as_ref().map(|bf| bf.is_real_browser),
-        "browser_engine": fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.engine.clone()),
-        "browser_version": fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.version.clone()),
-        "operating_system": fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.os.clone()),
-        "scan_pattern": format!("{:?}", fingerprint_result.timing_patterns.scan_pattern),
-        "burst_requests": fingerprint_result.timing_patterns.burst_requests,
-        "request_interval_ms": fingerprint_result.timing_patterns.request_interval_ms
-    });
-
-
-    if let Some(country_code) = &geo_location.country_code {
-        supabase_log_payload["country_code"] = json!(country_code);
-    }
-    if let Some(country_name) = &geo_location.country_name {
-        supabase_log_payload["country_name"] = json!(country_name);
-    }
-    if let Some(region_code) = &geo_location.region_code {
-        supabase_log_payload["region_code"] = json!(region_code);
-    }
-    if let Some(region_name) = &geo_location.region_name {
-        supabase_log_payload["region_name"] = json!(region_name);
-    }
-    if let Some(city) = &geo_location.city {
-        supabase_log_payload["city"] = json!(city);
-    }
-    if let Some(latitude) = geo_location.latitude {
-        supabase_log_payload["latitude"] = json!(latitude);
+    // Advanced-Fingerprinting- und GeoIP-Felder gehören inhaltlich zur Interaktion, nicht
+    // zum Log-Datensatz selbst - daher auf `interaction_data` statt auf Top-Level-Feldern.
+    interaction_data["scanner_type"] = json!(fingerprint_result.scanner_type);
+    interaction_data["tool_confidence"] = json!(fingerprint_result.tool_confidence);
+    interaction_data["threat_level"] = json!(format!("{:?}", fingerprint_result.threat_level));
+    interaction_data["is_real_browser"] = json!(fingerprint_result.browser_fingerprint.as_ref().map(|bf| bf.is_real_browser));
+    interaction_data["browser_engine"] = json!(fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.engine.clone()));
+    interaction_data["browser_version"] = json!(fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.version.clone()));
+    interaction_data["operating_system"] = json!(fingerprint_result.browser_fingerprint.as_ref().and_then(|bf| bf.os.clone()));
+    interaction_data["scan_pattern"] = json!(format!("{:?}", fingerprint_result.timing_patterns.scan_pattern));
+    interaction_data["burst_requests"] = json!(fingerprint_result.timing_patterns.burst_requests);
+    interaction_data["request_interval_ms"] = json!(fingerprint_result.timing_patterns.request_interval_ms);
+    interaction_data["geo_location"] = json!(geo_location);
+    if let Some(challenge) = &auth_challenge {
+        interaction_data["credential_attempt"] = json!(challenge.attempt);
     }
-    if let Some(longitude) = geo_location.longitude {
-        supabase_log_payload["longitude"] = json!(longitude);
-    }
-    if let Some(timezone) = &geo_location.timezone {
-        supabase_log_payload["timezone"] = json!(timezone);
-    }
-    if let Some(isp) = &geo_location.isp {
-        supabase_log_payload["isp"] = json!(isp);
-    }
-    if let Some(organization) = &geo_location.organization {
-        supabase_log_payload["organization"] = json!(organization);
+    interaction_data["session_id"] = json!(session_id);
+    interaction_data["attack_chain"] = json!(session_tracker::attack_chain(&client_ip));
+    // An dieser Anfrage ausgeliefertes Canary-Token (siehe `mime_decoy`) mitschreiben, damit
+    // eine spätere Verwendung des vermeintlich erbeuteten Secrets im Traffic auf IP und Pfad
+    // dieser Anfrage zurückgeführt werden kann.
+    if let Some(token) = &canary_token {
+        interaction_data["canary_token"] = json!(token);
     }
 
-    let supabase_table_url = format!("{}/rest/v1/attacker_logs", state.supabase_api_url);
-
-    match state.http_client
-        .post(&supabase_table_url)
-        .header("apikey", &state.supabase_service_role_key)
-        .header("Authorization", format!("Bearer {}", &state.supabase_service_role_key))
-        .header("Content-Type", "application/json")
-        .json(&supabase_log_payload)
-        .send()
-        .await
-    {
-        Ok(res) => {
-            let status_code = res.status();
-            if status_code.is_success() {
-                println!("Log erfolgreich in Supabase gespeichert. Status: {}", status_code);
-            } else {
-                eprintln!("Fehler beim Speichern des Logs in Supabase: Status {}", status_code);
-                if let Ok(body) = res.text().await {
-                    eprintln!("Antwort Body: {}", body);
-                }
-            }
-        },
-        Err(e) => {
-            eprintln!("Fehler beim Senden des Logs an Supabase: {:?}", e);
+    // --- 1. An alle konfigurierten LogSinks weiterleiten (Supabase, Datei, ...) ---
+    let attacker_log = AttackerLog {
+        source_ip: client_ip.clone(),
+        honeypot_type: "http".to_string(),
+        interaction_data: interaction_data.clone(),
+        status: "logged".to_string(),
+    };
+
+    for sink in &state.log_sinks {
+        if let Err(e) = sink.write(&attacker_log).await {
+            eprintln!("Fehler beim Schreiben des Logs in einen Sink: {:?}", e);
+            observability::record_sink_failure("http");
         }
     }
 
     // --- 2. Sende Daten an Python KI-Mockup und erhalte Desinformation ---
     let ki_api_endpoint = format!("{}/analyze/and-disinform/", state.python_ai_url);
 
-    let mut ki_payload = json!({
+    let ki_payload = json!({
         "source_ip": client_ip,
         "honeypot_type": "http",
-        "interaction_data": interaction_data,
+        "interaction_data": interaction_data, // enthält bereits die GeoIP-Daten
         "status": "logged"
     });
 
-    // Add GeoIP data to AI payload
-    ki_payload["geo_location"] = json!(geo_location);
-
     let mut disinformation_content = String::from("Ein unerwarteter Fehler ist aufgetreten. Die angeforderte Ressource konnte nicht gefunden werden.");
     let mut ki_response_raw = Value::Null;
 
-    match state.http_client
-        .post(&ki_api_endpoint)
-        .header("Content-Type", "application/json")
-        .json(&ki_payload)
-        .send()
-        .await
-    {
+    let ki_forward_started_at = std::time::Instant::now();
+    let ki_result = send_with_retry(&state.http_client_options, || {
+        state.http_client
+            .post(&ki_api_endpoint)
+            .header("Content-Type", "application/json")
+            .json(&ki_payload)
+            .send()
+    }).await;
+    observability::record_ai_forward_latency("http", ki_forward_started_at.elapsed());
+
+    match ki_result {
         Ok(res) => {
             let status_code = res.status();
             if status_code.is_success() {
@@ -402,48 +524,21 @@ async fn log_http_interaction(
         }
     }
 
-    // Rückgabe der Desinformation und der rohen KI-Antwort
-    (disinformation_content, ki_response_raw)
+    // Set-Cookie für neu eröffnete Sessions, damit Folgeanfragen derselben Angriffskette
+    // zugeordnet werden können (siehe `session_tracker`).
+    let session_cookie = is_new_session.then(|| session_tracker::session_cookie_header(&session_id));
+
+    // Rückgabe der Desinformation, der rohen KI-Antwort, des erkannten Scanner-Typs (für die
+    // Wahl des `DecoyProfile`), einer eventuellen Auth-Challenge (für geschützte Pfade) und
+    // eines Session-Cookies bei Erstkontakt
+    (disinformation_content, ki_response_raw, fingerprint_result.scanner_type, auth_challenge, session_cookie)
 }
 
-// NEU: Funktion zur Generierung einer dynamischen HTML-Antwort
-async fn generate_dynamic_html_response(disinformation_text: String) -> String {
-    let javascript_fingerprinting = generate_javascript_fingerprinting();
-    
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html lang="de">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Systemmeldung: Interner Fehler</title>
-    <style>
-        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; background-color: #f0f2f5; color: #333; margin: 0; padding: 20px; display: flex; justify-content: center; align-items: center; min-height: 100vh; }}
-        .container {{ background-color: #fff; padding: 30px; border-radius: 8px; box-shadow: 0 4px 12px rgba(0, 0, 0, 0.1); max-width: 600px; text-align: center; }}
-        h1 {{ color: #d32f2f; font-size: 2.5em; margin-bottom: 20px; }}
-        p {{ font-size: 1.1em; line-height: 1.6; color: #555; }}
-        .error-code {{ font-family: 'Consolas', monospace; background-color: #eee; padding: 5px 10px; border-radius: 4px; display: inline-block; margin-top: 15px; color: #777; }}
-        .disinfo-message {{ background-color: #e8f5e9; color: #388e3c; padding: 15px; border-left: 5px solid #4caf50; margin-top: 25px; border-radius: 4px; text-align: left; }}
-        .footer {{ margin-top: 30px; font-size: 0.9em; color: #888; }}
-    </style>
-    {}
-</head>
-<body>
-    <div class="container">
-        <h1>Zugriff verweigert oder Fehler</h1>
-        <p>Leider konnte Ihre Anfrage nicht wie gewünscht bearbeitet werden.</p>
-        <div class="disinfo-message">
-            <strong>Wichtige Systeminformationen:</strong><br>
-            {}
-        </div>
-        <p class="footer">Bitte kontaktieren Sie den Systemadministrator, falls Sie weitere Unterstützung benötigen.</p>
-    </div>
-</body>
-</html>"#,
-        javascript_fingerprinting,
-        disinformation_text
-    );
-    html
+// Rendert die Index-Seite des gewählten `DecoyProfile` und bettet dabei das
+// clientseitige Fingerprinting-Skript sowie die von der KI gelieferte Desinformation ein.
+async fn generate_dynamic_html_response(profile: DecoyProfile, disinformation_text: String, identity_token: &str) -> String {
+    let javascript_fingerprinting = generate_javascript_fingerprinting(identity_token);
+    profile.render_index(&disinformation_text, &javascript_fingerprinting)
 }
 
 // NEU: Funktion zur Filterung von Browser-spezifischen Anfragen
@@ -451,7 +546,6 @@ fn should_ignore_request(path: &str, headers: &HeaderMap) -> bool {
     // Liste der Pfade, die ignoriert werden sollen
     let ignore_paths = [
         "/favicon.ico",
-        "/robots.txt",
         "/sitemap.xml",
         "/apple-touch-icon.png",
         "/apple-touch-icon-precomposed.png",
@@ -480,15 +574,6 @@ fn should_ignore_request(path: &str, headers: &HeaderMap) -> bool {
     false
 }
 
-// NEU: Einfache 404-Antwort für ignorierte Anfragen
-async fn generate_simple_404() -> String {
-    r#"<!DOCTYPE html>
-<html>
-<head><title>404 Not Found</title></head>
-<body><h1>404 Not Found</h1><p>The requested resource was not found.</p></body>
-</html>"#.to_string()
-}
-
 // Enhanced attack request detection using advanced fingerprinting
 fn is_attack_request(path: &str, headers: &HeaderMap, body: &Option<String>) -> bool {
     let path_lower = path.to_lowercase();
@@ -1342,8 +1427,15 @@ fn calculate_threat_level(
 }
 
 // JavaScript fingerprinting payload generator
-fn generate_javascript_fingerprinting() -> String {
-    r#"
+fn generate_javascript_fingerprinting(identity_token: &str) -> String {
+    // Server-seitig aufgelöstes Identitäts-Token (siehe `identity_graph`) als JS-String-Literal
+    // einbetten - über `serde_json::to_string` statt eines rohen Format-Inserts, damit ein
+    // Cookie-Wert mit Anführungszeichen o.ä. nicht aus dem String-Kontext ausbrechen kann. Per
+    // `replace` statt `format!` eingesetzt, damit das restliche Skript nicht wegen der vielen
+    // `{`/`}` in JS-Syntax escaped werden muss.
+    let identity_token_literal = serde_json::to_string(identity_token).unwrap_or_else(|_| "\"\"".to_string());
+
+    let script = r#"
 <script>
 (function() {
     // Canvas fingerprinting
@@ -1377,34 +1469,152 @@ fn generate_javascript_fingerprinting() -> String {
     }
     
     // Audio fingerprinting
+    // Deterministisches Audio-Fingerprinting über OfflineAudioContext statt eines Live-Kontexts:
+    // ein Dreieck-Oszillator wird durch einen DynamicsCompressor mit fest verdrahteten Parametern
+    // gerendert, sodass das Ergebnis ausschließlich von Audio-Stack/Codec des Geräts abhängt, nicht
+    // von Timing oder Systemlast - emulierte/headless Audio-Implementierungen liefern dabei
+    // typischerweise eine konstante oder Null-Summe.
     function getAudioFingerprint() {
         return new Promise((resolve) => {
-            const audioContext = new (window.AudioContext || window.webkitAudioContext)();
-            const oscillator = audioContext.createOscillator();
-            const analyser = audioContext.createAnalyser();
-            const gainNode = audioContext.createGain();
-            
-            oscillator.connect(analyser);
-            analyser.connect(gainNode);
-            gainNode.connect(audioContext.destination);
-            
-            oscillator.frequency.value = 1000;
-            oscillator.start(0);
-            
-            setTimeout(() => {
-                const fingerprint = Array.from(new Uint8Array(analyser.frequencyBinCount))
-                    .reduce((acc, val) => acc + val, 0);
-                oscillator.stop();
-                resolve(fingerprint);
-            }, 100);
+            try {
+                const OfflineCtx = window.OfflineAudioContext || window.webkitOfflineAudioContext;
+                const context = new OfflineCtx(1, 44100 * 5, 44100);
+
+                const oscillator = context.createOscillator();
+                oscillator.type = 'triangle';
+                oscillator.frequency.value = 10000;
+
+                const compressor = context.createDynamicsCompressor();
+                compressor.threshold.value = -50;
+                compressor.knee.value = 40;
+                compressor.ratio.value = 12;
+                compressor.attack.value = 0;
+                compressor.release.value = 0.25;
+
+                oscillator.connect(compressor);
+                compressor.connect(context.destination);
+                oscillator.start(0);
+
+                context.oncomplete = (event) => {
+                    const samples = event.renderedBuffer.getChannelData(0);
+                    let sum = 0;
+                    for (let i = 4500; i < 5000; i++) {
+                        sum += Math.abs(samples[i]);
+                    }
+                    resolve(sum);
+                };
+                context.startRendering();
+            } catch (e) {
+                resolve(0);
+            }
         });
     }
-    
+
+    // Persistente Re-Identifikation: liest Cookie/localStorage/sessionStorage getrennt aus
+    // (mirroring the getCookie helper pattern), bevor ein einheitlicher Wert zurückgeschrieben
+    // wird - so bleibt sichtbar, welches der drei Signale beim Server ankommt, selbst wenn eines
+    // davon gelöscht wurde.
+    function getCookie(name) {
+        const match = document.cookie.match(new RegExp('(?:^|; )' + name + '=([^;]*)'));
+        return match ? decodeURIComponent(match[1]) : null;
+    }
+    function setCookie(name, value) {
+        document.cookie = name + '=' + encodeURIComponent(value) + '; path=/; max-age=31536000; samesite=lax';
+    }
+    function resolveIdentitySignals() {
+        const SERVER_IDENTITY_TOKEN = __IDENTITY_TOKEN__;
+        const cookieToken = getCookie('trapd_uid');
+        let localToken = null;
+        let sessionToken = null;
+        try { localToken = window.localStorage.getItem('trapd_uid'); } catch (e) {}
+        try { sessionToken = window.sessionStorage.getItem('trapd_uid'); } catch (e) {}
+
+        const resolvedToken = cookieToken || localToken || sessionToken || SERVER_IDENTITY_TOKEN;
+
+        setCookie('trapd_uid', resolvedToken);
+        try { window.localStorage.setItem('trapd_uid', resolvedToken); } catch (e) {}
+        try { window.sessionStorage.setItem('trapd_uid', resolvedToken); } catch (e) {}
+
+        return { cookieToken: cookieToken, localStorageToken: localToken, sessionStorageToken: sessionToken };
+    }
+
+    // Iframe-Abgleich: bettet `/fingerprint/iframe` (same-origin, vom selben Host) in einen
+    // unsichtbaren Iframe ein und wartet auf dessen `postMessage` mit den dort erneut ausgelesenen
+    // Navigator-/Screen-Werten. Ein Timeout liefert `null`, falls die Nachricht ausbleibt
+    // (z. B. weil Drittanbieter-Iframes blockiert sind), statt die Hauptmessung zu blockieren.
+    function collectIframeFingerprint() {
+        return new Promise(function(resolve) {
+            const iframe = document.createElement('iframe');
+            iframe.style.display = 'none';
+            iframe.src = '/fingerprint/iframe';
+
+            let settled = false;
+            function cleanup(result) {
+                if (settled) return;
+                settled = true;
+                window.removeEventListener('message', onMessage);
+                iframe.remove();
+                resolve(result);
+            }
+            function onMessage(event) {
+                if (event.data && event.data.type === 'trapd_iframe_fingerprint') {
+                    cleanup(event.data.data);
+                }
+            }
+
+            window.addEventListener('message', onMessage);
+            setTimeout(function() { cleanup(null); }, 1500);
+            document.body.appendChild(iframe);
+        });
+    }
+
+    // Worker-Gegenprobe: `Object.defineProperty`-Overrides, die ein Evasion-Toolkit auf
+    // `window.navigator` anwendet, propagieren nicht in den frischen globalen Scope eines neu
+    // erzeugten Workers - eine Abweichung zwischen Worker- und Main-Thread-Lesung ist daher ein
+    // starkes Indiz für Laufzeit-Manipulation statt für echte Geräte-Vielfalt.
+    function collectWorkerFingerprint() {
+        return new Promise(function(resolve) {
+            const workerBody = "self.onmessage = function() {" +
+                "self.postMessage({" +
+                "userAgent: navigator.userAgent," +
+                "platform: navigator.platform," +
+                "hardwareConcurrency: navigator.hardwareConcurrency," +
+                "timezone: Intl.DateTimeFormat().resolvedOptions().timeZone," +
+                "timingEntries: performance.getEntriesByType('resource').length" +
+                "});" +
+                "};";
+            let settled = false;
+            let worker;
+            try {
+                const blob = new Blob([workerBody], { type: 'application/javascript' });
+                worker = new Worker(URL.createObjectURL(blob));
+            } catch (e) {
+                resolve(null);
+                return;
+            }
+            function cleanup(result) {
+                if (settled) return;
+                settled = true;
+                worker.terminate();
+                resolve(result);
+            }
+            worker.onmessage = function(event) { cleanup(event.data); };
+            worker.onerror = function() { cleanup(null); };
+            setTimeout(function() { cleanup(null); }, 1500);
+            worker.postMessage('go');
+        });
+    }
+
     // Collect comprehensive fingerprint
     async function collectFingerprint() {
+        const identitySignals = resolveIdentitySignals();
         const fp = {
+            identitySignals: identitySignals,
             userAgent: navigator.userAgent,
             platform: navigator.platform,
+            appVersion: navigator.appVersion,
+            vendor: navigator.vendor,
+            productSub: navigator.productSub,
             language: navigator.language,
             languages: navigator.languages,
             timezone: Intl.DateTimeFormat().resolvedOptions().timeZone,
@@ -1414,16 +1624,22 @@ fn generate_javascript_fingerprinting() -> String {
                 colorDepth: screen.colorDepth,
                 pixelDepth: screen.pixelDepth
             },
+            innerWidth: window.innerWidth,
+            outerWidth: window.outerWidth,
             canvas: getCanvasFingerprint(),
             webgl: getWebGLFingerprint(),
             audio: await getAudioFingerprint(),
             plugins: Array.from(navigator.plugins).map(p => p.name),
+            mimeTypesLength: navigator.mimeTypes.length,
+            webdriver: !!navigator.webdriver,
             cookieEnabled: navigator.cookieEnabled,
             localStorage: !!window.localStorage,
             sessionStorage: !!window.sessionStorage,
             indexedDB: !!window.indexedDB,
             hardwareConcurrency: navigator.hardwareConcurrency,
             deviceMemory: navigator.deviceMemory,
+            iframeFingerprint: await collectIframeFingerprint(),
+            workerFingerprint: await collectWorkerFingerprint(),
             timestamp: Date.now()
         };
         
@@ -1443,6 +1659,8 @@ fn generate_javascript_fingerprinting() -> String {
     }
 })();
 </script>
-"#.to_string()
+"#;
+
+    script.replace("__IDENTITY_TOKEN__", &identity_token_literal)
 }
 
@@ -0,0 +1,112 @@
+// backend/rust_honeypots/src/identity_graph.rs
+//
+// Fingerprints driften bei Browser-Updates, ein gecleartes Cookie wiederum kappt jede
+// Wiedererkennung über `trapd_uid`. Dieses Modul hält einen Union-Find-artigen Graphen, der
+// jedes beobachtete Identitätssignal (Cookie-, localStorage- und sessionStorage-Token sowie die
+// aus `fingerprint_analysis` abgeleitete `visitor_id`) auf eine gemeinsame Identität abbildet:
+// verliert ein Angreifer sein Cookie, löst sich die Identität über den noch stabilen
+// Fingerprint auf; driftet der Fingerprint, löst sie sich über das noch vorhandene Token auf.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose, Engine as _};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+const IDENTITY_COOKIE_NAME: &str = "trapd_uid";
+
+// Obergrenze für die Anzahl gleichzeitig vorgehaltener Signal->Identität-Zuordnungen - ohne sie
+// könnte ein Angreifer durch beliebig viele verschiedene Signale im `/fingerprint`-POST den
+// Prozessspeicher unbegrenzt wachsen lassen. Gleiches Muster wie `geoip::GEOIP_CACHE_CAPACITY`.
+const IDENTITY_LINKS_CAPACITY: usize = 50_000;
+
+// Verweist jedes bekannte Signal (Token oder Fingerprint-ID) auf seinen aktuellen
+// "Repräsentanten" - die Wurzel der Union-Find-Struktur. Per LRU begrenzt statt einer
+// unbegrenzt wachsenden `HashMap`, siehe `geoip::GeoIpCache`.
+static IDENTITY_LINKS: Lazy<Arc<Mutex<LruCache<String, String>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(IDENTITY_LINKS_CAPACITY).unwrap()))));
+
+fn find_root(links: &mut LruCache<String, String>, id: &str) -> String {
+    let mut current = id.to_string();
+    while let Some(next) = links.get(&current).cloned() {
+        if next == current {
+            break;
+        }
+        current = next;
+    }
+    current
+}
+
+// Vergibt ein neues Identitäts-Token: SHA-256 über Quell-IP und aktuellen Zeitpunkt
+// (Nanosekunden), nicht aus einem Zufallsgenerator.
+fn generate_identity_token(source_ip: &str) -> String {
+    let nanos_since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(source_ip.as_bytes());
+    hasher.update(b"|identity|");
+    hasher.update(nanos_since_epoch.to_string().as_bytes());
+    general_purpose::URL_SAFE_NO_PAD.encode(&hasher.finalize()[..16])
+}
+
+// Nur Zeichen, die unsere eigenen Tokens auch tatsächlich erzeugen - ein Cookie-Wert, der davon
+// abweicht, wird verworfen statt roh in die ausgelieferte Seite eingebettet zu werden.
+fn is_plausible_token(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    let cookie_header = headers.get(axum::http::header::COOKIE)?.to_str().ok()?;
+    cookie_header.split(';').find_map(|pair| {
+        let (key, value) = pair.trim().split_once('=')?;
+        (key == name).then(|| value.to_string())
+    })
+}
+
+// Liefert das Identitäts-Token, das in die ausgelieferte Seite eingebettet wird: das vom Client
+// mitgeschickte Cookie, sofern plausibel, sonst ein frisch vergebenes (erster Kontakt).
+pub fn resolve_identity_token(headers: &HeaderMap, source_ip: &str) -> String {
+    cookie_value(headers, IDENTITY_COOKIE_NAME)
+        .filter(|value| is_plausible_token(value))
+        .unwrap_or_else(|| generate_identity_token(source_ip))
+}
+
+// Verknüpft die im `/fingerprint`-POST beobachteten Signale (Cookie, localStorage,
+// sessionStorage, Fingerprint-ID) zu einer gemeinsamen Identität und liefert deren
+// Repräsentanten zurück. Signale, die nicht wie eines unserer eigenen Tokens aussehen (leer,
+// zu lang, exotische Zeichen), werden verworfen statt roh in den Graphen aufgenommen zu werden -
+// dieselbe Prüfung wie für das Cookie in `resolve_identity_token`.
+pub fn merge_identity(signals: &[&str]) -> String {
+    let known: Vec<String> = signals
+        .iter()
+        .copied()
+        .filter(|signal| is_plausible_token(signal))
+        .map(|signal| signal.to_string())
+        .collect();
+    if known.is_empty() {
+        return String::new();
+    }
+
+    let mut links = IDENTITY_LINKS.lock().unwrap();
+    let roots: Vec<String> = known.iter().map(|id| find_root(&mut links, id)).collect();
+    let canonical = roots[0].clone();
+
+    for id in &known {
+        links.put(id.clone(), canonical.clone());
+    }
+    for root in &roots {
+        if root != &canonical {
+            links.put(root.clone(), canonical.clone());
+        }
+    }
+
+    canonical
+}
@@ -0,0 +1,81 @@
+// backend/rust_honeypots/src/lifecycle.rs
+//
+// main.rs startete HTTP- und SSH-Listener bisher als einzelne fire-and-forget
+// `tokio::spawn`s ohne Shutdown-Pfad: ein `unwrap()` auf einem fehlgeschlagenen Bind
+// riss den ganzen Prozess mit, und ein abstürzender Handler-Task wurde nie neu
+// gestartet. Dieses Modul bündelt ein Shutdown-Signal (SIGINT/SIGTERM über ein
+// `CancellationToken`) und einen Supervisor, der einen Honeypot-Listener mit
+// Backoff neu startet, solange kein Shutdown angefordert wurde.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+
+// Löst ab, sobald SIGINT oder SIGTERM empfangen wird. Wird an `axum::serve(...)
+// .with_graceful_shutdown(...)` bzw. als Abbruchbedingung in Listener-Loops durchgereicht.
+pub fn shutdown_token() -> CancellationToken {
+    let token = CancellationToken::new();
+    let signal_token = token.clone();
+    tokio::spawn(async move {
+        let mut sigterm = signal(SignalKind::terminate())
+            .expect("SIGTERM-Handler konnte nicht installiert werden");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                println!("Lifecycle: SIGINT empfangen, fahre herunter...");
+            }
+            _ = sigterm.recv() => {
+                println!("Lifecycle: SIGTERM empfangen, fahre herunter...");
+            }
+        }
+        signal_token.cancel();
+    });
+    token
+}
+
+// Startet `task` über `tokio::spawn` neu, solange `token` nicht abgebrochen wurde. Sowohl
+// ein Panic als auch ein normales Beenden der Future gilt als Absturz dieses Honeypots -
+// die anderen supervisierten Honeypots laufen davon unbeeinflusst weiter.
+pub async fn supervise<F, Fut>(name: &str, token: CancellationToken, mut task: F)
+where
+    F: FnMut(CancellationToken) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+    let mut backoff = Duration::from_millis(500);
+
+    while !token.is_cancelled() {
+        let handle = tokio::spawn(task(token.clone()));
+
+        match handle.await {
+            Ok(()) => {
+                if token.is_cancelled() {
+                    break;
+                }
+                eprintln!(
+                    "Lifecycle: Honeypot '{}' hat sich beendet, Neustart in {:?}",
+                    name, backoff
+                );
+            }
+            Err(e) => {
+                eprintln!(
+                    "Lifecycle: Honeypot '{}' ist abgestürzt ({:?}), Neustart in {:?}",
+                    name, e, backoff
+                );
+            }
+        }
+
+        if token.is_cancelled() {
+            break;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = token.cancelled() => break,
+        }
+        backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+    }
+
+    println!("Lifecycle: Honeypot '{}' beendet (Shutdown angefordert)", name);
+}
@@ -0,0 +1,310 @@
+// backend/rust_honeypots/src/log_sink.rs
+//
+// Entkoppelt die Honeypots von Supabase: jeder Honeypot schreibt nur noch gegen
+// `dyn LogSink`, Supabase ist eine von mehreren austauschbaren Implementierungen.
+
+use async_trait::async_trait;
+use fs2::FileExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+pub type SinkError = Box<dyn std::error::Error + Send + Sync>;
+pub type SinkResult<T> = Result<T, SinkError>;
+
+// Eine einzelne, normalisierte Log-Zeile, wie sie bislang 1:1 an Supabase gepostet wurde.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackerLog {
+    pub source_ip: String,
+    pub honeypot_type: String,
+    pub interaction_data: Value,
+    pub status: String,
+}
+
+#[async_trait]
+pub trait LogSink: Send + Sync {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()>;
+}
+
+// Der bisherige Pfad: REST-Insert in die `attacker_logs`-Tabelle via PostgREST.
+pub struct SupabaseSink {
+    http_client: Client,
+    table_url: String,
+    service_role_key: String,
+}
+
+impl SupabaseSink {
+    pub fn new(http_client: Client, supabase_api_url: &str, service_role_key: &str) -> Self {
+        Self {
+            http_client,
+            table_url: format!("{}/rest/v1/attacker_logs", supabase_api_url),
+            service_role_key: service_role_key.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LogSink for SupabaseSink {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()> {
+        let res = self
+            .http_client
+            .post(&self.table_url)
+            .header("apikey", &self.service_role_key)
+            .header("Authorization", format!("Bearer {}", &self.service_role_key))
+            .header("Content-Type", "application/json")
+            .json(record)
+            .send()
+            .await?;
+
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            Err(format!("Supabase antwortete mit {}: {}", status, body).into())
+        }
+    }
+}
+
+// Direkter Postgres-Insert für Betreiber, die keine Supabase-Instanz betreiben wollen.
+pub struct PostgresSink {
+    pool: sqlx::PgPool,
+}
+
+impl PostgresSink {
+    pub async fn connect(database_url: &str) -> SinkResult<Self> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl LogSink for PostgresSink {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()> {
+        sqlx::query(
+            "INSERT INTO attacker_logs (source_ip, honeypot_type, interaction_data, status) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&record.source_ip)
+        .bind(&record.honeypot_type)
+        .bind(&record.interaction_data)
+        .bind(&record.status)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+// Append-only Datei-Sink für Offline-/Air-Gapped-Deployments - ein JSON-Objekt pro Zeile.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl LogSink for FileSink {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+// Schiebt Records auf eine Redis-Liste, damit ein separater Worker sie asynchron verarbeiten kann.
+pub struct RedisQueueSink {
+    client: redis::Client,
+    queue_key: String,
+}
+
+impl RedisQueueSink {
+    pub fn new(redis_url: &str, queue_key: impl Into<String>) -> SinkResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(redis_url)?,
+            queue_key: queue_key.into(),
+        })
+    }
+}
+
+#[async_trait]
+impl LogSink for RedisQueueSink {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let payload = serde_json::to_string(record)?;
+        redis::cmd("LPUSH")
+            .arg(&self.queue_key)
+            .arg(payload)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+// Wrappt einen inneren Sink (typischerweise `SupabaseSink`) mit einem crash-sicheren
+// lokalen Spool: schlägt ein Write fehl, wird der Record statt verloren zu gehen atomar
+// (tempfile -> fsync -> rename, unter einem advisory Lock) ins Spool-Verzeichnis
+// geschrieben. Ein Hintergrund-Task spielt gespoolte Records periodisch erneut gegen den
+// inneren Sink ab und löscht sie erst nach erfolgreichem Replay - modelliert nach Hagrids
+// Filesystem-Datenbank.
+pub struct SpoolingSink<S: LogSink + 'static> {
+    inner: Arc<S>,
+    spool_dir: PathBuf,
+}
+
+impl<S: LogSink + 'static> SpoolingSink<S> {
+    pub fn new(inner: S, spool_dir: impl Into<PathBuf>, replay_interval: Duration) -> SinkResult<Self> {
+        let spool_dir = spool_dir.into();
+        std::fs::create_dir_all(&spool_dir)?;
+
+        let inner = Arc::new(inner);
+        let replay_inner = inner.clone();
+        let replay_dir = spool_dir.clone();
+        tokio::spawn(async move {
+            replay_loop(replay_inner, replay_dir, replay_interval).await;
+        });
+
+        Ok(Self { inner, spool_dir })
+    }
+
+    // Schreibt `record` atomar in den Spool: zuerst in eine `.tmp`-Datei im selben
+    // Verzeichnis, fsync, dann `rename` auf den endgültigen Namen, damit ein Reader nie
+    // eine halb geschriebene Datei sieht. Ein advisory Lock auf `.spool.lock` erlaubt es
+    // mehreren Honeypot-Prozessen, sich dasselbe Verzeichnis zu teilen.
+    async fn spool(&self, record: &AttackerLog) -> SinkResult<()> {
+        let spool_dir = self.spool_dir.clone();
+        let payload = serde_json::to_vec(record)?;
+        let file_stem = spool_file_stem(&record.source_ip);
+
+        tokio::task::spawn_blocking(move || -> SinkResult<()> {
+            let lock_path = spool_dir.join(".spool.lock");
+            let lock_file = std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .open(&lock_path)?;
+            lock_file.lock_exclusive()?;
+
+            let tmp_path = spool_dir.join(format!("{}.tmp", file_stem));
+            let final_path = spool_dir.join(format!("{}.json", file_stem));
+
+            let mut tmp_file = std::fs::File::create(&tmp_path)?;
+            tmp_file.write_all(&payload)?;
+            tmp_file.sync_all()?;
+            drop(tmp_file);
+
+            std::fs::rename(&tmp_path, &final_path)?;
+
+            FileExt::unlock(&lock_file)?;
+            Ok(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S: LogSink + 'static> LogSink for SpoolingSink<S> {
+    async fn write(&self, record: &AttackerLog) -> SinkResult<()> {
+        match self.inner.write(record).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let Err(spool_err) = self.spool(record).await {
+                    eprintln!(
+                        "SpoolingSink: Record konnte weder geschrieben noch gespoolt werden: {:?} / {:?}",
+                        e, spool_err
+                    );
+                    return Err(spool_err);
+                }
+                eprintln!("SpoolingSink: Write fehlgeschlagen ({:?}), Record im Spool gesichert", e);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn spool_file_stem(source_ip: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{}_{:09}-{}",
+        now.as_secs(),
+        now.subsec_nanos(),
+        source_ip.replace([':', '.'], "_")
+    )
+}
+
+async fn replay_loop<S: LogSink + 'static>(inner: Arc<S>, spool_dir: PathBuf, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = replay_once(&inner, &spool_dir).await {
+            eprintln!("SpoolingSink: Replay-Durchlauf fehlgeschlagen: {:?}", e);
+        }
+    }
+}
+
+async fn replay_once<S: LogSink + 'static>(inner: &Arc<S>, spool_dir: &PathBuf) -> SinkResult<()> {
+    let scan_dir = spool_dir.clone();
+    let mut entries = tokio::task::spawn_blocking(move || -> SinkResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&scan_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    })
+    .await??;
+
+    for path in entries.drain(..) {
+        let data = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("SpoolingSink: konnte Spool-Datei {:?} nicht lesen: {:?}", path, e);
+                continue;
+            }
+        };
+
+        let record: AttackerLog = match serde_json::from_slice(&data) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("SpoolingSink: ungültiger Spool-Eintrag {:?}, überspringe: {:?}", path, e);
+                continue;
+            }
+        };
+
+        match inner.write(&record).await {
+            Ok(()) => {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    eprintln!("SpoolingSink: konnte abgespielten Eintrag {:?} nicht löschen: {:?}", path, e);
+                }
+            }
+            Err(e) => {
+                eprintln!("SpoolingSink: Replay von {:?} erneut fehlgeschlagen: {:?}", path, e);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,30 +1,66 @@
-use axum::{
-    extract::{ConnectInfo, State, OriginalUri},
-    response::{Html, IntoResponse},
-    routing::{get, post},
-    Router,
-};
-use serde_json::{json, Value};
+mod auth_decoy;
+mod common;
+mod decoy;
+mod fingerprint_analysis;
+mod geoip;
+mod http_client;
+mod http_honeypot;
+mod identity_graph;
+mod lifecycle;
+mod log_sink;
+mod mime_decoy;
+mod observability;
+mod openapi_decoy;
+mod session_tracker;
+mod ssh_honeypot;
+
+use axum::{routing::get, Router};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use dotenv::dotenv;
 use std::env;
 use reqwest::Client;
-use axum::http::{HeaderMap, Method};
-use url::Url;
-
-// Datenstruktur für den HTTP-Client und Konfiguration
-#[derive(Clone)]
-struct AppState {
-    http_client: Client,
-    supabase_api_url: String,
-    supabase_service_role_key: String,
-    python_ai_url: String, // NEU: URL der Python KI
+use tokio_util::sync::CancellationToken;
+
+use common::{AppState, SharedAppState};
+use http_client::HttpClientOptions;
+use lifecycle::{shutdown_token, supervise};
+use log_sink::{FileSink, LogSink, SpoolingSink, SupabaseSink};
+
+// Standard-Intervall, in dem ein crash-sicherer Supabase-Spool (siehe `SUPABASE_SPOOL_DIR`)
+// versucht, liegengebliebene Records erneut zuzustellen.
+const SUPABASE_SPOOL_REPLAY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// Baut die konfigurierten LogSinks aus der Umgebung. Standard: nur Supabase, wie bisher.
+fn build_log_sinks(http_client: &Client, supabase_api_url: &str, supabase_service_role_key: &str) -> Vec<Arc<dyn LogSink>> {
+    let supabase_sink = SupabaseSink::new(http_client.clone(), supabase_api_url, supabase_service_role_key);
+
+    let mut sinks: Vec<Arc<dyn LogSink>> = Vec::new();
+    match env::var("SUPABASE_SPOOL_DIR") {
+        // Verlorene Supabase-Writes sind inakzeptabel, sobald ein Spool-Verzeichnis
+        // konfiguriert ist - wrappe den Sink, statt ihn unverändert danebenzustellen.
+        Ok(spool_dir) => match SpoolingSink::new(supabase_sink, spool_dir, SUPABASE_SPOOL_REPLAY_INTERVAL) {
+            Ok(spooling_sink) => sinks.push(Arc::new(spooling_sink)),
+            Err(e) => {
+                eprintln!("Konnte Supabase-Spool nicht initialisieren ({:?}), nutze Supabase ohne Spool", e);
+                sinks.push(Arc::new(SupabaseSink::new(http_client.clone(), supabase_api_url, supabase_service_role_key)));
+            }
+        },
+        Err(_) => sinks.push(Arc::new(supabase_sink)),
+    }
+
+    if let Ok(spool_path) = env::var("LOG_SPOOL_FILE") {
+        sinks.push(Arc::new(FileSink::new(spool_path)));
+    }
+
+    sinks
 }
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    observability::init_tracing("trapd");
 
     let supabase_api_url = env::var("SUPABASE_LOCAL_URL")
         .expect("SUPABASE_LOCAL_URL muss gesetzt sein");
@@ -34,170 +70,81 @@ async fn main() {
         .expect("PYTHON_AI_URL muss gesetzt sein");
 
 
-    let http_client = Client::new();
-
-    let app_state = AppState {
+    let http_client_options = HttpClientOptions::from_env();
+    let http_client = http_client_options.build();
+    let log_sinks = build_log_sinks(&http_client, &supabase_api_url, &supabase_service_role_key);
+
+    // Lokale GeoLite2-Datenbanken sind optional - ohne `GEOIP_CITY_DB_PATH` fällt
+    // `geoip::lookup_geoip` auf den bisherigen HTTP-Lookup gegen ip-api.com zurück.
+    let geoip_reader = env::var("GEOIP_CITY_DB_PATH")
+        .ok()
+        .and_then(|path| geoip::open_reader(&path))
+        .map(Arc::new);
+    let geoip_isp_reader = env::var("GEOIP_ISP_DB_PATH")
+        .ok()
+        .and_then(|path| geoip::open_reader(&path))
+        .map(Arc::new);
+    let geoip_cache = Arc::new(geoip::new_cache());
+    let geoip_provider = geoip::build_provider(http_client.clone());
+    let geoip_rate_limiter = Arc::new(geoip::build_rate_limiter());
+
+    let app_state: SharedAppState = Arc::new(AppState {
         http_client,
         supabase_api_url,
         supabase_service_role_key,
         python_ai_url, // Hinzugefügt
-    };
-
-    let app = Router::new()
-        .route("/", get(honeypot_handler))
-        .route("/*path", get(honeypot_handler))
-        .route("/", post(honeypot_handler_post))
-        .route("/*path", post(honeypot_handler_post))
-        .with_state(app_state);
+        log_sinks,
+        http_client_options,
+        geoip_reader,
+        geoip_isp_reader,
+        geoip_cache,
+        geoip_provider,
+        geoip_rate_limiter,
+    });
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    println!("HTTP Honeypot lauscht auf http://{}", addr);
+    // Gemeinsames Shutdown-Signal für alle Honeypots (SIGINT/SIGTERM), siehe `lifecycle`.
+    let shutdown = shutdown_token();
 
-    let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
-        .await
-        .unwrap();
-}
+    let http_state = app_state.clone();
+    let http_supervisor = supervise("http", shutdown.clone(), move |token| {
+        let state = http_state.clone();
+        async move { run_http_honeypot(state, token).await }
+    });
 
-// Handler für GET-Anfragen
-async fn honeypot_handler(
-    method: Method,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    State(state): State<AppState>,
-    uri: OriginalUri,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    log_and_forward_interaction(method, addr, state, uri, headers, None).await
-}
+    let ssh_state = app_state.clone();
+    let ssh_supervisor = supervise("ssh", shutdown.clone(), move |token| {
+        let state = ssh_state.clone();
+        async move { ssh_honeypot::start_ssh_honeypot(state, token).await }
+    });
 
-// Handler für POST-Anfragen
-async fn honeypot_handler_post(
-    method: Method,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    State(state): State<AppState>,
-    uri: OriginalUri,
-    headers: HeaderMap,
-    body: String,
-) -> impl IntoResponse {
-    log_and_forward_interaction(method, addr, state, uri, headers, Some(body)).await
+    tokio::join!(http_supervisor, ssh_supervisor);
+    println!("TRAPD: alle Honeypots sind beendet, Prozess fährt herunter.");
 }
 
-// Allgemeine Funktion zum Loggen und Weiterleiten von Interaktionen
-async fn log_and_forward_interaction(
-    method: Method,
-    addr: SocketAddr,
-    state: AppState,
-    uri: OriginalUri,
-    headers: HeaderMap,
-    request_body: Option<String>,
-) -> impl IntoResponse {
-    let client_ip = addr.ip().to_string();
-    let request_path = uri.path();
-    let user_agent = headers.get("User-Agent")
-        .and_then(|value| value.to_str().ok())
-        .unwrap_or("N/A");
-    let http_method = method.as_str();
-
-    let mut query_params = serde_json::Map::new();
-    if let Some(query) = uri.query() {
-        if let Ok(parsed_url) = Url::parse(&format!("http://dummy.com?{}", query)) {
-            for (key, value) in parsed_url.query_pairs() {
-                query_params.insert(key.into_owned(), Value::String(value.into_owned()));
-            }
-        }
-    }
-
-    println!("Honeypot-Interaktion: IP: {}, Methode: {}, Pfad: {}, User-Agent: {}", client_ip, http_method, request_path, user_agent);
-    if let Some(body) = &request_body {
-        println!("Request Body: {}", body);
-    }
-
-    // Daten für die Datenbank und die KI
-    let mut interaction_data = json!({
-        "request_path": request_path,
-        "method": http_method,
-        "user_agent": user_agent,
-        "headers": headers.iter().map(|(k, v)| {
-            (k.to_string(), Value::String(v.to_str().unwrap_or("").to_string()))
-        }).collect::<serde_json::Map<String, Value>>(),
-        "query_parameters": query_params,
-    });
-
-    if let Some(body) = request_body.clone() { // Clone, da Body für Supabase und KI benötigt wird
-        interaction_data["request_body"] = Value::String(body);
-    }
+// Startet den axum-Router für den HTTP-Honeypot und beendet sich, sobald `shutdown`
+// ausgelöst wird - `with_graceful_shutdown` lässt dabei laufende Requests zu Ende laufen.
+async fn run_http_honeypot(app_state: SharedAppState, shutdown: CancellationToken) {
+    let app = Router::new()
+        .route("/metrics", get(observability::metrics_handler))
+        .merge(http_honeypot::create_http_router(app_state));
 
-    // --- 1. Logge in Supabase ---
-    let supabase_log_payload = json!({
-        "source_ip": client_ip,
-        "honeypot_type": "http",
-        "interaction_data": interaction_data, // Nutze die vollständigen Daten hier
-        "status": "logged"
-    });
+    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    println!("HTTP Honeypot lauscht auf http://{}", addr);
 
-    let supabase_table_url = format!("{}/rest/v1/attacker_logs", state.supabase_api_url);
-
-    match state.http_client
-        .post(&supabase_table_url)
-        .header("apikey", &state.supabase_service_role_key)
-        .header("Authorization", format!("Bearer {}", &state.supabase_service_role_key))
-        .header("Content-Type", "application/json")
-        .json(&supabase_log_payload)
-        .send()
-        .await
-    {
-        Ok(res) => {
-            if res.status().is_success() {
-                println!("Log erfolgreich in Supabase gespeichert. Status: {}", res.status());
-            } else {
-                eprintln!("Fehler beim Speichern des Logs in Supabase: Status {}", res.status());
-                if let Ok(body) = res.text().await {
-                    eprintln!("Antwort Body: {}", body);
-                }
-            }
-        },
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
         Err(e) => {
-            eprintln!("Fehler beim Senden des Logs an Supabase: {:?}", e);
+            eprintln!("HTTP Honeypot konnte nicht gebunden werden: {:?}", e);
+            return;
         }
-    }
+    };
 
-    // --- 2. Sende Daten an Python KI-Mockup ---
-    let ki_payload = json!({
-        "source_ip": client_ip,
-        "honeypot_type": "http",
-        "interaction_data": interaction_data, // Sende die gleichen umfassenden Daten an die KI
-        "status": "logged" // Status könnte von Honeypot immer 'logged' sein
-    });
+    let result = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await;
 
-    let ki_api_endpoint = format!("{}/analyze-and-disinform/", state.python_ai_url);
-
-    match state.http_client
-        .post(&ki_api_endpoint)
-        .header("Content-Type", "application/json")
-        .json(&ki_payload)
-        .send()
-        .await
-    {
-        Ok(res) => {
-            if res.status().is_success() {
-                println!("Daten erfolgreich an Python KI-Mockup gesendet. Status: {}", res.status());
-                if let Ok(ki_response_body) = res.json::<Value>().await {
-                    println!("Antwort von KI-Mockup: {:?}", ki_response_body);
-                    // Hier könntest du die Desinformation weiterverarbeiten
-                    // oder basierend darauf eine spezifischere Honeypot-Antwort generieren.
-                }
-            } else {
-                eprintln!("Fehler beim Senden an Python KI-Mockup: Status {}", res.status());
-                if let Ok(body) = res.text().await {
-                    eprintln!("Antwort Body von KI-Mockup: {}", body);
-                }
-            }
-        },
-        Err(e) => {
-            eprintln!("Fehler beim Senden der Anfrage an Python KI-Mockup: {:?}", e);
-        }
+    if let Err(e) = result {
+        eprintln!("HTTP Honeypot Serve-Fehler: {:?}", e);
     }
+}
 
-    // Dummy-Antwort an den Angreifer (könnte später durch KI-Antwort beeinflusst werden)
-    Html("<!DOCTYPE html><html><head><title>404 Not Found</title></head><body><h1>Not Found</h1><p>The requested URL was not found on this server.</p></body></html>")
-}
\ No newline at end of file
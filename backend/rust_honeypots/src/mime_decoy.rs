@@ -0,0 +1,121 @@
+// backend/rust_honeypots/src/mime_decoy.rs
+//
+// `honeypot_handler` antwortet unabhängig vom angefragten Pfad immer mit HTML - ein Scanner,
+// der `/.env`, `/config.php` oder `/backup.sql` abruft, erkennt den Honeypot sofort an der
+// falschen Content-Type und dem HTML-Body. Dieses Modul portiert die Idee von Servos
+// `mime_classifier`: anhand der Dateiendung im angefragten Pfad wird ein plausibler
+// `Content-Type` gewählt und passender Decoy-Inhalt generiert - inklusive eines eindeutigen,
+// an die Quell-IP gebundenen Canary-Tokens, über das eine spätere Verwendung der
+// vermeintlich erbeuteten Secrets im Traffic wiedererkannt werden kann.
+
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecoyKind {
+    DotEnv,
+    PhpConfig,
+    SqlDump,
+    JsonConfig,
+    RobotsTxt,
+}
+
+impl DecoyKind {
+    // Wählt anhand von Dateiname und Endung des angefragten Pfads den passenden Decoy-Typ,
+    // oder `None`, wenn der Pfad kein bekanntes "interessantes" Dateiformat anfragt (in dem
+    // Fall bleibt es bei der normalen `DecoyProfile`-HTML-Antwort).
+    pub fn from_path(path: &str) -> Option<Self> {
+        let file_name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+
+        if file_name == "robots.txt" {
+            return Some(DecoyKind::RobotsTxt);
+        }
+        if file_name == ".env" || file_name.ends_with(".env") {
+            return Some(DecoyKind::DotEnv);
+        }
+        if file_name.ends_with(".sql") {
+            return Some(DecoyKind::SqlDump);
+        }
+        if file_name.ends_with(".php") {
+            return Some(DecoyKind::PhpConfig);
+        }
+        if file_name.ends_with(".json") || file_name.ends_with(".yml") || file_name.ends_with(".yaml") {
+            return Some(DecoyKind::JsonConfig);
+        }
+
+        None
+    }
+
+    // Fallback für Uploads ohne erkennbare Dateiendung im Pfad: sniffed die führenden Magic
+    // Bytes des Bodys, analog zu Servos `mime_classifier`, der ebenfalls anhand von
+    // Byte-Signaturen statt nur der Dateiendung klassifiziert.
+    pub fn from_body(body: &[u8]) -> Option<Self> {
+        let leading = &body[..body.len().min(32)];
+        let leading_trimmed = leading.iter().copied().skip_while(|b| b.is_ascii_whitespace()).collect::<Vec<u8>>();
+
+        if leading_trimmed.starts_with(b"<?php") {
+            return Some(DecoyKind::PhpConfig);
+        }
+        if leading_trimmed.starts_with(b"-- MySQL dump") || leading_trimmed.starts_with(b"SQLite format 3\0") {
+            return Some(DecoyKind::SqlDump);
+        }
+        if leading_trimmed.starts_with(b"{") || leading_trimmed.starts_with(b"[") {
+            return Some(DecoyKind::JsonConfig);
+        }
+
+        None
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DecoyKind::DotEnv => "text/plain; charset=utf-8",
+            DecoyKind::PhpConfig => "application/octet-stream",
+            DecoyKind::SqlDump => "application/sql",
+            DecoyKind::JsonConfig => "application/json",
+            DecoyKind::RobotsTxt => "text/plain; charset=utf-8",
+        }
+    }
+
+    // Generiert den Decoy-Inhalt mit eingebettetem Canary-Token - `robots.txt` verrät keine
+    // Secrets und bekommt daher keinen Token.
+    pub fn render(&self, canary_token: &str) -> String {
+        match self {
+            DecoyKind::DotEnv => format!(
+                "DB_HOST=127.0.0.1\nDB_NAME=production\nDB_USER=admin\nDB_PASSWORD={token}\nAPP_ENV=production\nAPP_DEBUG=false\nAWS_ACCESS_KEY_ID=AKIA{token}\nAWS_SECRET_ACCESS_KEY={token}\n",
+                token = canary_token
+            ),
+            DecoyKind::PhpConfig => format!(
+                "<?php\ndefine('DB_HOST', 'localhost');\ndefine('DB_USER', 'root');\ndefine('DB_PASSWORD', '{token}');\ndefine('DB_NAME', 'wordpress');\ndefine('AUTH_KEY', '{token}');\n",
+                token = canary_token
+            ),
+            DecoyKind::SqlDump => format!(
+                "-- MySQL dump 10.13  Distrib 8.0.31, for Linux (x86_64)\n--\n-- Host: localhost    Database: production\nINSERT INTO `users` (`id`, `username`, `password`, `api_token`) VALUES\n(1, 'admin', '$2y$10$abcdefghijklmnopqrstuv', '{token}'),\n(2, 'support', '$2y$10$abcdefghijklmnopqrstuv', '{token}');\n-- Dump truncated --\n",
+                token = canary_token
+            ),
+            DecoyKind::JsonConfig => format!(
+                r#"{{"database":{{"host":"127.0.0.1","user":"admin","password":"{token}"}},"api_key":"{token}"}}"#,
+                token = canary_token
+            ),
+            DecoyKind::RobotsTxt => "User-agent: *\nDisallow: /admin/\nDisallow: /backup/\nDisallow: /.env\nDisallow: /config.php\n".to_string(),
+        }
+    }
+}
+
+// Erzeugt ein an Quell-IP und Pfad gebundenes Canary-Token, über das eine spätere Verwendung
+// der vermeintlich erbeuteten Secrets im Traffic wiedererkannt werden kann.
+pub fn canary_token(source_ip: &str, path: &str) -> String {
+    let nanos_since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(source_ip.as_bytes());
+    hasher.update(b"|");
+    hasher.update(path.as_bytes());
+    hasher.update(b"|");
+    hasher.update(nanos_since_epoch.to_string().as_bytes());
+    let digest = hasher.finalize();
+
+    general_purpose::URL_SAFE_NO_PAD.encode(&digest[..12])
+}
@@ -0,0 +1,137 @@
+// backend/rust_honeypots/src/observability.rs
+//
+// Bisher gab es keine Instrumentierung jenseits von println!/eprintln!. Dieses Modul
+// bündelt das OTLP-Tracing-Setup (`tracing`-Subscriber plus OTLP-Exporter) und
+// Prometheus-Metriken, die über einen `/metrics`-Endpoint exportiert werden. Die
+// Honeypot-Handler erzeugen selbst keine Spans; exportiert wird nur, was über `tracing`
+// gemeldet wird.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use prometheus::{HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+// Obergrenze für die Anzahl gleichzeitig nachverfolgter Quell-IPs (siehe
+// `DISTINCT_SOURCE_IPS_SEEN`) - gleiches Muster wie `session_tracker::SESSIONS_CAPACITY`.
+const DISTINCT_SOURCE_IPS_CAPACITY: usize = 50_000;
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+static INTERACTIONS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("trapd_interactions_total", "Anzahl Honeypot-Interaktionen"),
+        &["honeypot_type"],
+    )
+    .expect("trapd_interactions_total registrieren");
+    REGISTRY.register(Box::new(counter.clone())).ok();
+    counter
+});
+
+// Eine Zeitreihe pro beobachteter Quell-IP (`trapd_source_ip_requests_total{source_ip=...}`)
+// würde bei unbegrenzt vielen Scanner-IPs zu unbegrenztem Label-Cardinality-Wachstum führen -
+// genau das, was dieser Honeypot provoziert. Stattdessen nur die Anzahl *unterschiedlicher*
+// Quell-IPs als Gauge, gespeist aus einem per LRU begrenzten Set (siehe `record_interaction`).
+static DISTINCT_SOURCE_IPS_TOTAL: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new("trapd_distinct_source_ips", "Anzahl unterschiedlicher, zuletzt beobachteter Quell-IPs")
+        .expect("trapd_distinct_source_ips registrieren");
+    REGISTRY.register(Box::new(gauge.clone())).ok();
+    gauge
+});
+
+static DISTINCT_SOURCE_IPS_SEEN: Lazy<Arc<Mutex<LruCache<String, ()>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(DISTINCT_SOURCE_IPS_CAPACITY).unwrap()))));
+
+static AI_FORWARD_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    let histogram = HistogramVec::new(
+        prometheus::HistogramOpts::new(
+            "trapd_ai_forward_latency_seconds",
+            "Latenz des Forwardings an das KI-Mockup",
+        ),
+        &["honeypot_type"],
+    )
+    .expect("trapd_ai_forward_latency_seconds registrieren");
+    REGISTRY.register(Box::new(histogram.clone())).ok();
+    histogram
+});
+
+static SINK_FAILURES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new("trapd_sink_failures_total", "Fehlgeschlagene LogSink-Schreibversuche"),
+        &["sink"],
+    )
+    .expect("trapd_sink_failures_total registrieren");
+    REGISTRY.register(Box::new(counter.clone())).ok();
+    counter
+});
+
+// Initialisiert `tracing` einmal pro Binary: immer ein stdout-Fmt-Layer, zusätzlich ein
+// OTLP-Exporter, sofern `OTEL_EXPORTER_OTLP_ENDPOINT` gesetzt ist.
+pub fn init_tracing(service_name: &str) {
+    let fmt_layer = tracing_subscriber::fmt::layer();
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let otlp_layer = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().map(|endpoint| {
+        let exporter = opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(endpoint);
+
+        let provider = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(exporter)
+            .install_batch(opentelemetry_sdk::runtime::Tokio)
+            .expect("OTLP-Tracer-Provider konnte nicht gebaut werden");
+
+        let tracer = provider.tracer(service_name.to_string());
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(otlp_layer)
+        .init();
+}
+
+pub fn record_interaction(honeypot_type: &str, source_ip: &str) {
+    INTERACTIONS_TOTAL.with_label_values(&[honeypot_type]).inc();
+
+    let mut seen = DISTINCT_SOURCE_IPS_SEEN.lock().unwrap();
+    seen.put(source_ip.to_string(), ());
+    DISTINCT_SOURCE_IPS_TOTAL.set(seen.len() as i64);
+}
+
+pub fn record_ai_forward_latency(honeypot_type: &str, latency: Duration) {
+    AI_FORWARD_LATENCY_SECONDS
+        .with_label_values(&[honeypot_type])
+        .observe(latency.as_secs_f64());
+}
+
+pub fn record_sink_failure(sink: &str) {
+    SINK_FAILURES_TOTAL.with_label_values(&[sink]).inc();
+}
+
+// `/metrics`-Handler im Prometheus-Textformat, für jedes Binary über `create_http_router`
+// bzw. den jeweiligen `main` einzuhängen.
+pub async fn metrics_handler() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let metric_families = REGISTRY.gather();
+    match encoder.encode_to_string(&metric_families) {
+        Ok(body) => (
+            [("Content-Type", "text/plain; version=0.0.4")],
+            body,
+        ),
+        Err(e) => (
+            [("Content-Type", "text/plain; version=0.0.4")],
+            format!("# Fehler beim Encodieren der Metriken: {:?}", e),
+        ),
+    }
+}
@@ -0,0 +1,446 @@
+// backend/rust_honeypots/src/openapi_decoy.rs
+//
+// Scanner-Traffic gegen /api, /swagger.json, /openapi.json oder /rest/ bekam bislang
+// dieselbe feste HTML-Fehlerseite wie jeder andere Pfad - ein API-Fuzzer erkennt das schon
+// an der ersten Antwort und bricht die Interaktion ab. Dieses Modul lädt ein
+// OpenAPI-3.0-Dokument (per Default eine eingebettete Fake-Spec, überschreibbar über
+// `OPENAPI_DECOY_SPEC_PATH`) und synthetisiert daraus Routen, die schema-konsistente
+// Beispiel-Antworten liefern - inklusive der ursprünglichen Spec unter `/openapi.json` und
+// `/swagger.json` -, sodass der Fuzzer eine plausible REST-API vor sich zu haben glaubt und
+// weiter sein Verhalten preisgibt.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, OriginalUri, Path as AxumPath, Query, State};
+use axum::http::{HeaderMap, Method, StatusCode, Version};
+use axum::response::IntoResponse;
+use axum::routing::{on, MethodFilter};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+
+use crate::common::SharedAppState;
+use crate::http_honeypot::log_http_interaction;
+
+#[derive(Debug, Clone)]
+struct Operation {
+    method: Method,
+    status: StatusCode,
+    content_type: String,
+    example: Value,
+}
+
+// Die synthetisierte Fake-API: das rohe OpenAPI-Dokument (für `/openapi.json`) plus eine
+// pro axum-Pfad gruppierte Liste der Operationen, aus denen die Routen gebaut werden.
+pub struct ApiSurface {
+    document: Value,
+    routes: HashMap<String, Vec<Operation>>,
+}
+
+impl ApiSurface {
+    pub fn load() -> Self {
+        let document = std::env::var("OPENAPI_DECOY_SPEC_PATH")
+            .ok()
+            .and_then(|path| std::fs::read_to_string(&path).ok())
+            .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+            .unwrap_or_else(default_spec);
+
+        let routes = parse_routes(&document);
+        Self { document, routes }
+    }
+
+    // Hängt `/openapi.json`, `/swagger.json` und eine Route pro im Dokument definiertem
+    // Pfad an `router` an. Muss aufgerufen werden, bevor `router.with_state(...)` den
+    // State-Typ auf `()` festlegt.
+    pub fn register_routes(&self, mut router: Router<SharedAppState>) -> Router<SharedAppState> {
+        let spec_for_openapi = self.document.clone();
+        let spec_for_swagger = self.document.clone();
+        router = router
+            .route("/openapi.json", axum::routing::get(move || async move { Json(spec_for_openapi) }))
+            .route("/swagger.json", axum::routing::get(move || async move { Json(spec_for_swagger) }));
+
+        for (axum_path, operations) in &self.routes {
+            let Some(first) = operations.first() else { continue };
+            let filter = operations[1..]
+                .iter()
+                .fold(method_filter(&first.method), |acc, op| acc | method_filter(&op.method));
+
+            let operations = Arc::new(operations.clone());
+            router = router.route(
+                axum_path,
+                on(filter, move |method: Method,
+                                  ConnectInfo(addr): ConnectInfo<SocketAddr>,
+                                  State(state): State<SharedAppState>,
+                                  uri: OriginalUri,
+                                  headers: HeaderMap,
+                                  http_version: Version,
+                                  AxumPath(path_params): AxumPath<HashMap<String, String>>,
+                                  Query(query_params): Query<HashMap<String, String>>,
+                                  body: String| {
+                    let operations = operations.clone();
+                    async move {
+                        serve_operation(
+                            operations,
+                            method,
+                            addr,
+                            state,
+                            uri,
+                            headers,
+                            http_version,
+                            path_params,
+                            query_params,
+                            body,
+                        )
+                        .await
+                    }
+                }),
+            );
+        }
+
+        router
+    }
+}
+
+// Liefert die zur aufgerufenen Methode passende Beispiel-Antwort und loggt die Anfrage
+// genau wie jede andere Honeypot-Interaktion über `log_http_interaction`.
+async fn serve_operation(
+    operations: Arc<Vec<Operation>>,
+    method: Method,
+    addr: SocketAddr,
+    state: SharedAppState,
+    uri: OriginalUri,
+    headers: HeaderMap,
+    http_version: Version,
+    path_params: HashMap<String, String>,
+    query_params: HashMap<String, String>,
+    body: String,
+) -> impl IntoResponse {
+    let request_body = if body.is_empty() { None } else { Some(body) };
+    log_http_interaction(method.clone(), addr, state, uri, headers, http_version, request_body, None).await;
+
+    let Some(operation) = operations.iter().find(|op| op.method == method) else {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [("content-type", "application/json")],
+            json!({"error": "method not allowed"}).to_string(),
+        )
+            .into_response();
+    };
+
+    let mut example = operation.example.clone();
+    honor_parameters(&mut example, &path_params, &query_params);
+
+    (
+        operation.status,
+        [("content-type", operation.content_type.as_str())],
+        serde_json::to_string(&example).unwrap_or_default(),
+    )
+        .into_response()
+}
+
+// Spiegelt vom Scanner mitgegebene Pfad-/Query-Parameter in die generierte Beispiel-Antwort
+// zurück, sofern ein gleichnamiges Feld existiert - ein Scanner, der `/api/v1/users/42`
+// abfragt, soll `"id": 42` sehen statt des generischen Beispielwerts.
+fn honor_parameters(example: &mut Value, path_params: &HashMap<String, String>, query_params: &HashMap<String, String>) {
+    let Value::Object(fields) = example else { return };
+    for (key, raw_value) in path_params.iter().chain(query_params.iter()) {
+        if let Some(existing) = fields.get_mut(key) {
+            *existing = coerce_like(existing, raw_value);
+        }
+    }
+}
+
+fn coerce_like(existing: &Value, raw: &str) -> Value {
+    match existing {
+        Value::Number(_) => raw.parse::<i64>().map(Value::from).unwrap_or_else(|_| Value::String(raw.to_string())),
+        Value::Bool(_) => raw.parse::<bool>().map(Value::Bool).unwrap_or_else(|_| Value::String(raw.to_string())),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+fn method_filter(method: &Method) -> MethodFilter {
+    match *method {
+        Method::GET => MethodFilter::GET,
+        Method::POST => MethodFilter::POST,
+        Method::PUT => MethodFilter::PUT,
+        Method::DELETE => MethodFilter::DELETE,
+        Method::PATCH => MethodFilter::PATCH,
+        Method::HEAD => MethodFilter::HEAD,
+        Method::OPTIONS => MethodFilter::OPTIONS,
+        _ => MethodFilter::GET,
+    }
+}
+
+fn parse_routes(document: &Value) -> HashMap<String, Vec<Operation>> {
+    let mut routes: HashMap<String, Vec<Operation>> = HashMap::new();
+
+    let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+        return routes;
+    };
+
+    for (spec_path, path_item) in paths {
+        let Some(path_item) = path_item.as_object() else { continue };
+        let axum_path = to_axum_path(spec_path);
+
+        for (method_name, operation) in path_item {
+            let Some(method) = parse_method(method_name) else { continue };
+            let Some(op) = build_operation(method, operation, document) else { continue };
+            routes.entry(axum_path.clone()).or_default().push(op);
+        }
+    }
+
+    routes
+}
+
+// OpenAPI nutzt `{id}` für Pfad-Parameter, axum erwartet `:id`.
+fn to_axum_path(spec_path: &str) -> String {
+    spec_path
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with('{') && segment.ends_with('}') {
+                format!(":{}", &segment[1..segment.len() - 1])
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_method(name: &str) -> Option<Method> {
+    match name.to_ascii_lowercase().as_str() {
+        "get" => Some(Method::GET),
+        "post" => Some(Method::POST),
+        "put" => Some(Method::PUT),
+        "delete" => Some(Method::DELETE),
+        "patch" => Some(Method::PATCH),
+        _ => None,
+    }
+}
+
+fn build_operation(method: Method, operation: &Value, document: &Value) -> Option<Operation> {
+    let responses = operation.get("responses")?.as_object()?;
+    // Bevorzugt die erste 2xx-Antwort, wie ein echter Server sie im Erfolgsfall liefern würde.
+    let (status_key, response) = responses
+        .iter()
+        .find(|(code, _)| code.starts_with('2'))
+        .or_else(|| responses.iter().next())?;
+    let status = status_key
+        .parse::<u16>()
+        .ok()
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .unwrap_or(StatusCode::OK);
+
+    let content = response.get("content").and_then(Value::as_object);
+    let (content_type, schema) = content
+        .and_then(|c| c.get("application/json").map(|schema| ("application/json".to_string(), schema)))
+        .or_else(|| content.and_then(|c| c.iter().next().map(|(ct, schema)| (ct.clone(), schema))))
+        .map(|(ct, entry)| (ct, entry.get("schema")))
+        .unwrap_or(("application/json".to_string(), None));
+
+    let example = schema.and_then(|s| example_for_schema(s, document)).unwrap_or_else(|| json!({}));
+
+    Some(Operation { method, status, content_type, example })
+}
+
+// Generiert rekursiv einen Beispielwert aus einem (teil-)aufgelösten JSON-Schema. Ein
+// explizites `example`-Feld gewinnt immer, sonst wird anhand des `type` ein plausibler
+// Platzhalter gewählt.
+fn example_for_schema(schema: &Value, document: &Value) -> Option<Value> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return example_for_schema(resolve_ref(reference, document)?, document);
+    }
+
+    if let Some(example) = schema.get("example") {
+        return Some(example.clone());
+    }
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") => {
+            let mut fields = serde_json::Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, prop_schema) in properties {
+                    fields.insert(name.clone(), example_for_schema(prop_schema, document).unwrap_or(Value::Null));
+                }
+            }
+            Some(Value::Object(fields))
+        }
+        Some("array") => {
+            let item = schema
+                .get("items")
+                .and_then(|items| example_for_schema(items, document))
+                .unwrap_or(Value::Null);
+            Some(Value::Array(vec![item]))
+        }
+        Some("integer") => Some(json!(1)),
+        Some("number") => Some(json!(1.0)),
+        Some("boolean") => Some(json!(true)),
+        Some("string") => {
+            if let Some(first_enum_value) = schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()) {
+                return Some(first_enum_value.clone());
+            }
+            let text = schema
+                .get("format")
+                .and_then(Value::as_str)
+                .map(default_string_for_format)
+                .unwrap_or_else(|| "string".to_string());
+            Some(json!(text))
+        }
+        _ => Some(Value::Null),
+    }
+}
+
+fn default_string_for_format(format: &str) -> String {
+    match format {
+        "date-time" => "2024-01-01T00:00:00Z".to_string(),
+        "date" => "2024-01-01".to_string(),
+        "email" => "user@example.com".to_string(),
+        "uuid" => "00000000-0000-0000-0000-000000000000".to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+// Löst nur lokale Komponenten-Referenzen wie "#/components/schemas/User" auf - für eine
+// Fake-Spec gibt es keinen Grund, entfernte `$ref`-URLs nachzuladen.
+fn resolve_ref<'a>(reference: &str, document: &'a Value) -> Option<&'a Value> {
+    let path = reference.strip_prefix("#/")?;
+    let mut current = document;
+    for segment in path.split('/') {
+        current = current.get(segment)?;
+    }
+    Some(current)
+}
+
+// Default-Spec nach dem Vorbild der fatcat-openapi-Dateien: eine kleine, aber plausible
+// interne REST-API, wie sie Scanner häufig hinter `/api/v1/...` vermuten.
+fn default_spec() -> Value {
+    json!({
+        "openapi": "3.0.0",
+        "info": { "title": "Internal API Gateway", "version": "2.3.1" },
+        "paths": {
+            "/api/v1/users": {
+                "get": {
+                    "summary": "List users",
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/User" } }
+                                }
+                            }
+                        }
+                    }
+                },
+                "post": {
+                    "summary": "Create user",
+                    "responses": {
+                        "201": {
+                            "description": "Created",
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/User" } }
+                            }
+                        }
+                    }
+                }
+            },
+            "/api/v1/users/{id}": {
+                "get": {
+                    "summary": "Get user by id",
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/User" } }
+                            }
+                        }
+                    }
+                }
+            },
+            "/api/v1/products": {
+                "get": {
+                    "summary": "List products",
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Product" } }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "/api/v1/products/{id}": {
+                "get": {
+                    "summary": "Get product by id",
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Product" } }
+                            }
+                        }
+                    }
+                }
+            },
+            "/api/v1/login": {
+                "post": {
+                    "summary": "Authenticate",
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/AuthToken" } }
+                            }
+                        },
+                        "401": {
+                            "description": "Unauthorized",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": { "error": { "type": "string", "example": "invalid_credentials" } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "User": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "example": 1 },
+                        "username": { "type": "string", "example": "jdoe" },
+                        "email": { "type": "string", "format": "email" },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "is_admin": { "type": "boolean", "example": false }
+                    }
+                },
+                "Product": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "example": 101 },
+                        "name": { "type": "string", "example": "Widget" },
+                        "price": { "type": "number", "example": 19.99 },
+                        "in_stock": { "type": "boolean", "example": true }
+                    }
+                },
+                "AuthToken": {
+                    "type": "object",
+                    "properties": {
+                        "access_token": { "type": "string", "format": "uuid" },
+                        "token_type": { "type": "string", "example": "Bearer" },
+                        "expires_in": { "type": "integer", "example": 3600 }
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,135 @@
+// backend/rust_honeypots/src/session_tracker.rs
+//
+// `TIMING_TRACKER` beobachtet bereits Zeitabstände pro Quell-IP, aber jede Anfrage wird
+// ansonsten isoliert geloggt - ein Scanlauf aus Recon (`/`, `/robots.txt`), Login-Versuch
+// (`/wp-login.php`) und Shell-Upload (`/uploader.php`) taucht als drei unzusammenhängende
+// Log-Zeilen auf, statt als eine zusammenhängende Angriffskette erkennbar zu sein. Dieses
+// Modul bringt, angelehnt an Servos `cookie_storage`, einen Session-Store pro Quell-IP: beim
+// ersten Kontakt wird eine Session-ID vergeben (per `Set-Cookie` an den Client ausgeliefert),
+// danach wird ein Sliding-Window aus Pfad/Methode/Payload pro Anfrage mitgeschrieben, aus dem
+// sich die vollständige Angriffskette rekonstruieren lässt.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use axum::http::HeaderValue;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const SESSION_COOKIE_NAME: &str = "trapd_sid";
+// Anzahl der zuletzt beobachteten Anfragen, die pro Session vorgehalten werden.
+const SESSION_HISTORY_WINDOW: usize = 50;
+// Obergrenze für die Anzahl gleichzeitig vorgehaltener Sessions - ohne sie kann ein
+// internetweit erreichbarer Honeypot durch beliebig viele Scanner-IPs den Prozessspeicher
+// unbegrenzt wachsen lassen. Gleiches Muster wie `identity_graph::IDENTITY_LINKS_CAPACITY`
+// und `geoip::GEOIP_CACHE_CAPACITY`.
+const SESSIONS_CAPACITY: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestRecord {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    // Körper der Anfrage, auf eine handhabbare Länge gekürzt - für Login-Formulare und kleine
+    // Payloads reicht das, um den Schritt in der Angriffskette nachvollziehbar zu machen.
+    pub payload: Option<String>,
+}
+
+struct Session {
+    session_id: String,
+    history: Vec<RequestRecord>,
+}
+
+// Per LRU begrenzt statt einer unbegrenzt wachsenden `HashMap`, siehe `SESSIONS_CAPACITY`.
+static SESSIONS_BY_IP: Lazy<Arc<Mutex<LruCache<String, Session>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(SESSIONS_CAPACITY).unwrap()))));
+
+// Vergibt eine neue, an die Quell-IP und den aktuellen Zeitpunkt gebundene Session-ID.
+fn generate_session_id(source_ip: &str) -> String {
+    let nanos_since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(source_ip.as_bytes());
+    hasher.update(b"|");
+    hasher.update(nanos_since_epoch.to_string().as_bytes());
+    let digest = hasher.finalize();
+
+    general_purpose::URL_SAFE_NO_PAD.encode(&digest[..16])
+}
+
+const MAX_PAYLOAD_LOG_LEN: usize = 512;
+
+fn truncate_payload(payload: &str) -> String {
+    if payload.len() <= MAX_PAYLOAD_LOG_LEN {
+        payload.to_string()
+    } else {
+        // Byte-Index `MAX_PAYLOAD_LOG_LEN` kann mitten in einem Mehrbyte-UTF-8-Zeichen liegen -
+        // `char_indices` findet die letzte gültige Zeichengrenze davor statt roh zu slicen.
+        let cut = payload
+            .char_indices()
+            .map(|(i, _)| i)
+            .take_while(|&i| i <= MAX_PAYLOAD_LOG_LEN)
+            .last()
+            .unwrap_or(0);
+        format!("{}...", &payload[..cut])
+    }
+}
+
+// Trägt eine Anfrage in die Session der Quell-IP ein und legt bei Erstkontakt eine neue
+// Session an. Liefert die Session-ID sowie, ob diese Anfrage die Session neu eröffnet hat
+// (der Aufrufer setzt dann das `Set-Cookie`-Header).
+pub fn record_request(source_ip: &str, method: &str, path: &str, payload: Option<&str>) -> (String, bool) {
+    let mut sessions = SESSIONS_BY_IP.lock().unwrap();
+    let is_new_session = !sessions.contains(source_ip);
+
+    if is_new_session {
+        sessions.put(
+            source_ip.to_string(),
+            Session {
+                session_id: generate_session_id(source_ip),
+                history: Vec::new(),
+            },
+        );
+    }
+
+    let session = sessions.get_mut(source_ip).expect("gerade eingefügt oder bereits vorhanden");
+
+    session.history.push(RequestRecord {
+        timestamp: Utc::now(),
+        method: method.to_string(),
+        path: path.to_string(),
+        payload: payload.map(truncate_payload),
+    });
+
+    if session.history.len() > SESSION_HISTORY_WINDOW {
+        let overflow = session.history.len() - SESSION_HISTORY_WINDOW;
+        session.history.drain(0..overflow);
+    }
+
+    (session.session_id.clone(), is_new_session)
+}
+
+// Liefert die bisher beobachtete Angriffskette (chronologisch) der Session einer Quell-IP.
+pub fn attack_chain(source_ip: &str) -> Vec<RequestRecord> {
+    let mut sessions = SESSIONS_BY_IP.lock().unwrap();
+    sessions
+        .get(source_ip)
+        .map(|session| session.history.clone())
+        .unwrap_or_default()
+}
+
+// Baut das `Set-Cookie`-Header für eine neu eröffnete Session.
+pub fn session_cookie_header(session_id: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax",
+        SESSION_COOKIE_NAME, session_id
+    ))
+    .unwrap_or_else(|_| HeaderValue::from_static("trapd_sid=invalid"))
+}
@@ -1,150 +1,418 @@
 // backend/rust_honeypots/src/ssh_honeypot.rs
 
 use std::net::SocketAddr;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use russh::server::{Auth, Msg, Session};
+use russh::{Channel, ChannelId};
+use russh_keys::key::KeyPair;
 use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
 use crate::common::SharedAppState;
-use std::time::Duration; // Für Read-Timeout
-use std::convert::TryInto; // Für TryInto
+use crate::http_client::send_with_retry;
+use crate::log_sink::AttackerLog;
+use crate::observability;
 
-// Öffentliche Funktion zum Starten des SSH-Honeypots
-pub async fn start_ssh_honeypot(app_state: SharedAppState) {
+// Wie lange auf bereits angenommene Verbindungen gewartet wird, bevor beim Shutdown
+// hart abgebrochen wird.
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// Öffentliche Funktion zum Starten des SSH-Honeypots. Läuft, bis `shutdown` ausgelöst
+// wird; danach werden keine neuen Verbindungen mehr angenommen und auf die bereits
+// laufenden wird noch `SHUTDOWN_DRAIN_TIMEOUT` lang gewartet.
+pub async fn start_ssh_honeypot(app_state: SharedAppState, shutdown: CancellationToken) {
     let ssh_addr = SocketAddr::from(([0, 0, 0, 0], 2222)); // SSH auf Port 2222
-    println!("SSH Honeypot lauscht auf http://{}", ssh_addr);
+    println!("SSH Honeypot lauscht auf ssh://{}", ssh_addr);
+
+    // Ein frischer Ed25519-Hostkey pro Prozessstart reicht für einen Honeypot -
+    // echte Angreifer vergleichen den Host-Key-Fingerprint ohnehin selten.
+    let server_key = KeyPair::generate_ed25519().expect("Konnte keinen SSH-Hostkey generieren");
 
-    let listener = TcpListener::bind(ssh_addr).await.unwrap();
+    let config = Arc::new(russh::server::Config {
+        auth_rejection_time: std::time::Duration::from_millis(300),
+        keys: vec![server_key],
+        ..Default::default()
+    });
+
+    let listener = match TcpListener::bind(ssh_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("SSH Honeypot konnte nicht gebunden werden: {:?}", e);
+            return;
+        }
+    };
+
+    let mut connections = JoinSet::new();
 
     loop {
-        match listener.accept().await {
-            Ok((socket, client_addr)) => {
-                println!("SSH Honeypot: Neue Verbindung von {}", client_addr);
-                let state = app_state.clone();
-                tokio::spawn(async move {
-                    handle_ssh_connection(socket, client_addr, state).await;
-                });
-            },
-            Err(e) => eprintln!("SSH Listener Fehler: {:?}", e),
+        tokio::select! {
+            accept_result = listener.accept() => {
+                match accept_result {
+                    Ok((socket, client_addr)) => {
+                        println!("SSH Honeypot: Neue Verbindung von {}", client_addr);
+                        let state = app_state.clone();
+                        let config = config.clone();
+                        connections.spawn(async move {
+                            handle_ssh_connection(socket, client_addr, config, state).await;
+                        });
+                    }
+                    Err(e) => eprintln!("SSH Listener Fehler: {:?}", e),
+                }
+            }
+            _ = shutdown.cancelled() => {
+                println!("SSH Honeypot: Shutdown angefordert, stoppe Annahme neuer Verbindungen");
+                break;
+            }
         }
     }
-}
 
-// Funktion zur Bearbeitung einer einzelnen SSH-Verbindung
-async fn handle_ssh_connection(mut stream: TcpStream, client_addr: SocketAddr, state: SharedAppState) {
-    let client_ip = client_addr.ip().to_string();
-    let client_port = client_addr.port();
-
-    // Schritt 1: Senden des Server-Banners
-    // Wir senden immer noch einen SSH-Banner, da Clients das erwarten.
-    let server_banner = "SSH-2.0-OpenSSH_7.6p1 EchoChamber-Honeypot\r\n"; // Angepasster Banner
-    if let Err(e) = stream.write_all(server_banner.as_bytes()).await {
-        eprintln!("Fehler beim Senden des SSH Banners an {}: {:?}", client_addr, e);
-        let _ = stream.shutdown().await;
+    if connections.is_empty() {
         return;
     }
 
-    // Schritt 2: Empfangen des Client-Banners (und erster Daten)
-    let mut client_banner_buf = vec![0; 255]; // Max SSH banner length is 255
-    let client_data_raw = match tokio::time::timeout(
-        Duration::from_secs(2), // Gebe dem Client 2 Sekunden Zeit, den Banner zu senden
-        stream.read(&mut client_banner_buf)
-    ).await {
-        Ok(Ok(n)) => String::from_utf8_lossy(&client_banner_buf[..n]).into_owned(),
-        _ => String::from("No client banner or read error"),
+    println!(
+        "SSH Honeypot: warte auf {} aktive Verbindung(en) (max. {:?})",
+        connections.len(),
+        SHUTDOWN_DRAIN_TIMEOUT
+    );
+    let drain_deadline = tokio::time::sleep(SHUTDOWN_DRAIN_TIMEOUT);
+    tokio::pin!(drain_deadline);
+
+    loop {
+        tokio::select! {
+            next = connections.join_next() => {
+                if next.is_none() {
+                    break;
+                }
+            }
+            _ = &mut drain_deadline => {
+                eprintln!("SSH Honeypot: Drain-Timeout erreicht, breche verbleibende Verbindungen ab");
+                connections.abort_all();
+                break;
+            }
+        }
+    }
+}
+
+// Übergibt die Verbindung an russh, nachdem das rohe KEXINIT des Clients für das
+// spätere HASSH-Fingerprinting mitgeschnitten wurde (siehe `KexSniffStream`).
+async fn handle_ssh_connection(
+    socket: TcpStream,
+    client_addr: SocketAddr,
+    config: Arc<russh::server::Config>,
+    app_state: SharedAppState,
+) {
+    let client_kexinit = Arc::new(Mutex::new(None));
+    let sniff_stream = KexSniffStream::new(socket, client_kexinit.clone());
+
+    let handler = SshSession {
+        app_state,
+        peer_addr: Some(client_addr),
+        client_kexinit,
+        auth_attempts: 0,
+        last_username: None,
+        last_password: None,
     };
 
-    println!("SSH Honeypot: Client-Banner erhalten: {}", client_data_raw.trim());
+    if let Err(e) = russh::server::run_stream(config, sniff_stream, handler).await {
+        eprintln!("SSH-Session mit {} beendet: {:?}", client_addr, e);
+    }
+}
 
-    // Rudimentäre Erkennung von Anmeldedaten im Rohdatenstrom
-    let username_attempt = extract_from_raw(&client_data_raw, "user", "username").unwrap_or("unknown".to_string());
-    let password_attempt = extract_from_raw(&client_data_raw, "pass", "password").unwrap_or("unknown".to_string());
-    let login_method = if client_data_raw.contains("ssh-connection") { "ssh_client_attempt" } else { "raw_tcp_interception" };
+// Zustand einer einzelnen SSH-Verbindung, von der Banner-Phase bis zur Fake-Session.
+struct SshSession {
+    app_state: SharedAppState,
+    peer_addr: Option<SocketAddr>,
+    // Vom `KexSniffStream` befüllt, sobald das KEXINIT des Clients vollständig gelesen wurde.
+    client_kexinit: Arc<Mutex<Option<ClientKexInit>>>,
+    auth_attempts: u32,
+    last_username: Option<String>,
+    last_password: Option<String>,
+}
+
+#[async_trait]
+impl russh::server::Handler for SshSession {
+    type Error = russh::Error;
 
+    async fn auth_none(self, _user: &str) -> Result<(Self, Auth), Self::Error> {
+        // Wir wollen echte Zugangsdaten sehen, also lehnen wir passwortlose Anmeldung grundsätzlich ab.
+        Ok((self, Auth::Reject { proceed_with_methods: None }))
+    }
+
+    // Erste Passwort-Eingabe wird immer abgelehnt, die zweite angenommen - so bekommen
+    // wir garantiert mindestens ein glaubwürdiges (aber nicht zwingend korrektes) Paar
+    // und trotzdem genug Versuche, um Credential-Stuffing-Muster zu erkennen.
+    async fn auth_password(mut self, user: &str, password: &str) -> Result<(Self, Auth), Self::Error> {
+        self.auth_attempts += 1;
+        self.last_username = Some(user.to_string());
+        self.last_password = Some(password.to_string());
+
+        log_credential_attempt(&self, user, password).await;
+
+        if self.auth_attempts < 2 {
+            Ok((self, Auth::Reject { proceed_with_methods: None }))
+        } else {
+            Ok((self, Auth::Accept))
+        }
+    }
+
+    async fn channel_open_session(
+        self,
+        channel: Channel<Msg>,
+        session: Session,
+    ) -> Result<(Self, bool, Session), Self::Error> {
+        let _ = channel;
+        Ok((self, true, session))
+    }
+
+    // Sobald der Client eine Shell/PTY anfordert, geben wir eine Fake-Meldung aus und
+    // trennen die Verbindung - wir wollen Zugangsdaten sehen, keinen echten Remote-Zugriff geben.
+    async fn shell_request(
+        self,
+        channel: ChannelId,
+        mut session: Session,
+    ) -> Result<(Self, Session), Self::Error> {
+        let banner = b"Last login: Tue Jan 14 09:21:03 2026 from 10.0.0.4\r\n\
+            -bash: /usr/bin/motd: Permission denied\r\n\
+            Connection to server lost.\r\n";
+        session.data(channel, banner.to_vec().into());
+        session.close(channel);
+        Ok((self, session))
+    }
+}
+
+// Loggt einen einzelnen Credential-Versuch (noch vor Akzeptanz/Ablehnung) in Supabase/KI.
+async fn log_credential_attempt(conn: &SshSession, username: &str, password: &str) {
+    let Some(client_addr) = conn.peer_addr else {
+        return;
+    };
+    let client_ip = client_addr.ip().to_string();
+
+    let (hassh, hassh_algorithms) = match conn.client_kexinit.lock().await.as_ref() {
+        Some(kex) => compute_hassh(kex),
+        None => (String::new(), String::new()),
+    };
 
-    // Daten für Supabase und KI vorbereiten
     let interaction_data = json!({
-        "client_banner": client_data_raw.trim(),
-        "username_attempt": username_attempt,
-        "password_attempt": password_attempt,
-        "login_method": login_method,
+        "username": username,
+        "password": password,
+        "attempt_number": conn.auth_attempts,
+        "login_method": "ssh2_userauth",
         "client_ip": client_ip,
-        "client_port": client_port,
+        "client_port": client_addr.port(),
+        "hassh": hassh,
+        "hassh_algorithms": hassh_algorithms,
     });
 
-    // Logge in Supabase und sende an KI.
-    // Die KI wird die Desinformation formulieren, um auf eine HTTP-Seite zu verweisen.
-    let (disinformation_content, _ki_response_raw) = log_ssh_interaction(interaction_data, client_addr, state.clone()).await;
-    
-    // Die Antwort an den SSH-Client wird einfach ein generischer Fehler sein,
-    // da wir die Desinformation über HTTP liefern.
-    let response_message = format!("Authentication failed. Please check server status at http://{}:8080/system-status?ref={}\r\n", client_ip, "your_session_id_here"); // Dummy-ID
-    // Hier können wir die Desinformation in den Query-Parameter einbetten
-    let encoded_disinfo = urlencoding::encode(&disinformation_content).into_owned();
-    let final_response_message = format!("Authentication failed. For more information, please visit http://{}:8080/system-status?details={}\r\n", client_ip, encoded_disinfo);
+    let _ = log_ssh_interaction(interaction_data, client_addr, conn.app_state.clone()).await;
+}
+
+// Die vier client->server Namenslisten aus dem KEXINIT, in genau der vom Client
+// gesendeten Reihenfolge - https://github.com/salesforce/hassh#how-does-it-work
+#[derive(Debug, Clone, Default)]
+pub struct ClientKexInit {
+    pub kex_algorithms: Vec<String>,
+    pub encryption_algorithms_client_to_server: Vec<String>,
+    pub mac_algorithms_client_to_server: Vec<String>,
+    pub compression_algorithms_client_to_server: Vec<String>,
+}
+
+// Berechnet den HASSH-Fingerprint: die vier Namenslisten, Semikolon-getrennt,
+// dann die lowercase-hex MD5-Summe. Leere Listen bleiben als leere Felder erhalten.
+fn compute_hassh(kex: &ClientKexInit) -> (String, String) {
+    let hassh_algorithms = format!(
+        "{};{};{};{}",
+        kex.kex_algorithms.join(","),
+        kex.encryption_algorithms_client_to_server.join(","),
+        kex.mac_algorithms_client_to_server.join(","),
+        kex.compression_algorithms_client_to_server.join(","),
+    );
+
+    let digest = md5::compute(hassh_algorithms.as_bytes());
+    let hassh = format!("{:x}", digest);
+
+    (hassh, hassh_algorithms)
+}
+
+// Liest das SSH-Binärpaket aus `payload` (ohne Längenfeld/Padding) und extrahiert die
+// KEXINIT-Namenslisten. `payload[0]` muss `SSH_MSG_KEXINIT` (20) sein.
+fn parse_kexinit_payload(payload: &[u8]) -> Option<ClientKexInit> {
+    if payload.first() != Some(&20) {
+        return None;
+    }
+    // 1 Byte Message-Code + 16 Byte Cookie
+    let mut cursor = 1 + 16;
+
+    let mut read_namelist = |data: &[u8], pos: &mut usize| -> Option<Vec<String>> {
+        if data.len() < *pos + 4 {
+            return None;
+        }
+        let len = u32::from_be_bytes(data[*pos..*pos + 4].try_into().ok()?) as usize;
+        *pos += 4;
+        if data.len() < *pos + len {
+            return None;
+        }
+        let raw = std::str::from_utf8(&data[*pos..*pos + len]).ok()?;
+        *pos += len;
+        Some(if raw.is_empty() {
+            Vec::new()
+        } else {
+            raw.split(',').map(|s| s.to_string()).collect()
+        })
+    };
+
+    let kex_algorithms = read_namelist(payload, &mut cursor)?;
+    let _server_host_key_algorithms = read_namelist(payload, &mut cursor)?;
+    let encryption_algorithms_client_to_server = read_namelist(payload, &mut cursor)?;
+    let _encryption_algorithms_server_to_client = read_namelist(payload, &mut cursor)?;
+    let mac_algorithms_client_to_server = read_namelist(payload, &mut cursor)?;
+    let _mac_algorithms_server_to_client = read_namelist(payload, &mut cursor)?;
+    let compression_algorithms_client_to_server = read_namelist(payload, &mut cursor)?;
+
+    Some(ClientKexInit {
+        kex_algorithms,
+        encryption_algorithms_client_to_server,
+        mac_algorithms_client_to_server,
+        compression_algorithms_client_to_server,
+    })
+}
 
+// Tee'd die rohen Bytes eines `TcpStream` und schneidet nebenbei das erste SSH-Binärpaket
+// (das unverschlüsselte KEXINIT direkt nach dem Banner) für das HASSH-Fingerprinting mit,
+// ohne das normale Lesen durch `russh` zu beeinflussen.
+struct KexSniffStream {
+    inner: TcpStream,
+    sink: Arc<Mutex<Option<ClientKexInit>>>,
+    scratch: Vec<u8>,
+    done: bool,
+}
 
-    if let Err(e) = stream.write_all(final_response_message.as_bytes()).await {
-        eprintln!("Fehler beim Senden der Antwort an {}: {:?}", client_addr, e);
+impl KexSniffStream {
+    fn new(inner: TcpStream, sink: Arc<Mutex<Option<ClientKexInit>>>) -> Self {
+        Self {
+            inner,
+            sink,
+            scratch: Vec::new(),
+            done: false,
+        }
+    }
+
+    // Versucht, das erste Binärpaket nach der Banner-Zeile zu parsen, sobald genug
+    // Bytes angesammelt wurden. Reichen die Bytes noch nicht, kehrt sie ohne Wirkung zurück und
+    // `done` bleibt `false` - der nächste Read versucht es erneut.
+    fn try_capture(&mut self) {
+        let Some(banner_end) = find_crlf(&self.scratch) else {
+            return;
+        };
+        let packet_start = banner_end + 2;
+        if self.scratch.len() < packet_start + 4 {
+            return;
+        }
+        let packet_len = u32::from_be_bytes(
+            self.scratch[packet_start..packet_start + 4].try_into().unwrap(),
+        ) as usize;
+        let packet_end = packet_start + 4 + packet_len;
+        // `packet_end` kann bei `packet_len == 0` gleich `packet_start + 4` sein - dann reicht
+        // der Längen-Guard allein nicht, der folgende Zugriff auf `padding_len` braucht ein
+        // weiteres Byte danach.
+        if self.scratch.len() < packet_end || self.scratch.len() < packet_start + 5 {
+            return;
+        }
+        let padding_len = self.scratch[packet_start + 4] as usize;
+        let payload_start = packet_start + 5;
+        let payload_end = packet_end.saturating_sub(padding_len);
+        if payload_end <= payload_start || payload_end > self.scratch.len() {
+            self.done = true;
+            return;
+        }
+
+        if let Some(kex) = parse_kexinit_payload(&self.scratch[payload_start..payload_end]) {
+            let sink = self.sink.clone();
+            tokio::spawn(async move {
+                *sink.lock().await = Some(kex);
+            });
+        }
+        self.done = true;
     }
-    
-    // Verbindung sauber schließen
-    let _ = stream.shutdown().await;
 }
 
-// Helper function to extract simple key-value from raw string, if found
-fn extract_from_raw(raw_data: &str, key_prefix: &str, _default_value: &str) -> Option<String> {
-    let lower_raw = raw_data.to_lowercase();
-    if let Some(start_idx) = lower_raw.find(key_prefix) {
-        let after_key = &raw_data[start_idx + key_prefix.len()..];
-        if let Some(val_start_idx) = after_key.find(|c: char| c == '=' || c.is_whitespace()) {
-            let value_part = &after_key[val_start_idx..];
-            if let Some(val_end_idx) = value_part.find('\n') {
-                return Some(value_part[..val_end_idx].trim().to_string());
+fn find_crlf(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|w| w == b"\r\n")
+}
+
+impl AsyncRead for KexSniffStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let inner = Pin::new(&mut self.inner);
+        let result = inner.poll_read(cx, buf);
+
+        if !self.done {
+            if let Poll::Ready(Ok(())) = &result {
+                self.scratch.extend_from_slice(&buf.filled()[before..]);
+                // Genug Banner-Implementierungen senden die Banner-Zeile und das KEXINIT
+                // in getrennten TCP-Segmenten, daher sammeln wir über mehrere Reads.
+                if self.scratch.len() > 4096 {
+                    self.done = true; // Sicherheitsnetz gegen unbegrenztes Puffern
+                } else {
+                    self.try_capture();
+                }
             }
         }
+
+        result
     }
-    None
 }
 
+impl AsyncWrite for KexSniffStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
 
-// Funktion zum Loggen und Weiterleiten von SSH-Interaktionen (Unverändert)
-async fn log_ssh_interaction(interaction_data: Value, client_addr: SocketAddr, state: SharedAppState) -> (String, Value) { // Rückgabe von String und Value
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+// Funktion zum Loggen von SSH-Interaktionen in alle konfigurierten LogSinks und zum
+// Weiterleiten an das KI-Mockup (mit Retry über `send_with_retry`)
+async fn log_ssh_interaction(interaction_data: Value, client_addr: SocketAddr, state: SharedAppState) -> (String, Value) {
     let client_ip = client_addr.ip().to_string();
+    observability::record_interaction("ssh", &client_ip);
 
     println!("SSH Honeypot: Logge SSH-Interaktion von {}", client_ip);
 
-    // --- 1. Logge in Supabase (attacker_logs) ---
-    let supabase_log_payload = json!({
-        "source_ip": client_ip,
-        "honeypot_type": "ssh",
-        "interaction_data": interaction_data,
-        "status": "logged"
-    });
+    // --- 1. An alle konfigurierten LogSinks weiterleiten (Supabase, Datei, ...) ---
+    let attacker_log = AttackerLog {
+        source_ip: client_ip.clone(),
+        honeypot_type: "ssh".to_string(),
+        interaction_data: interaction_data.clone(),
+        status: "logged".to_string(),
+    };
 
-    let supabase_table_url = format!("{}/rest/v1/attacker_logs", state.supabase_api_url);
-
-    match state.http_client
-        .post(&supabase_table_url)
-        .header("apikey", &state.supabase_service_role_key)
-        .header("Authorization", format!("Bearer {}", &state.supabase_service_role_key))
-        .header("Content-Type", "application/json")
-        .json(&supabase_log_payload)
-        .send()
-        .await
-    {
-        Ok(res) => {
-            let status_code = res.status();
-            if status_code.is_success() {
-                println!("SSH Log erfolgreich in Supabase gespeichert. Status: {}", status_code);
-            } else {
-                eprintln!("Fehler beim Speichern des SSH Logs in Supabase: Status {}", status_code);
-                if let Ok(body) = res.text().await {
-                    eprintln!("Antwort Body: {}", body);
-                }
-            }
-        },
-        Err(e) => {
-            eprintln!("Fehler beim Senden des SSH Logs an Supabase: {:?}", e);
+    for sink in &state.log_sinks {
+        if let Err(e) = sink.write(&attacker_log).await {
+            eprintln!("Fehler beim Schreiben des SSH Logs in einen Sink: {:?}", e);
+            observability::record_sink_failure("ssh");
         }
     }
 
@@ -161,13 +429,17 @@ async fn log_ssh_interaction(interaction_data: Value, client_addr: SocketAddr, s
     let mut disinformation_content = String::from("Authentication failed. Try again.");
     let mut ki_response_raw = Value::Null;
 
-    match state.http_client
-        .post(&ki_api_endpoint)
-        .header("Content-Type", "application/json")
-        .json(&ki_payload)
-        .send()
-        .await
-    {
+    let ki_forward_started_at = std::time::Instant::now();
+    let ki_result = send_with_retry(&state.http_client_options, || {
+        state.http_client
+            .post(&ki_api_endpoint)
+            .header("Content-Type", "application/json")
+            .json(&ki_payload)
+            .send()
+    }).await;
+    observability::record_ai_forward_latency("ssh", ki_forward_started_at.elapsed());
+
+    match ki_result {
         Ok(res) => {
             let status_code = res.status();
             if status_code.is_success() {
@@ -194,7 +466,7 @@ async fn log_ssh_interaction(interaction_data: Value, client_addr: SocketAddr, s
         },
         Err(e) => {
             eprintln!("Fehler beim Senden der Anfrage an Python KI-Mockup: {:?}", e);
-            disinformation_content = "KI-Fehler: Konnte keine Antwort erhalten.".to_string(); // Fallback for network errors
+            disinformation_content = "KI-Fehler: Konnte keine Antwort erhalten.".to_string();
         }
     }
     (disinformation_content, ki_response_raw)
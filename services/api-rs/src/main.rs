@@ -1,16 +1,22 @@
 
+use async_trait::async_trait;
 use axum::{
-    extract::{State, Json},
+    extract::{FromRequestParts, State, Json},
+    http::request::Parts,
+    response::{IntoResponse, Response},
     routing::{get, post},
-    Router, http::StatusCode,
+    Router, http::{HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 use dotenvy::dotenv;
 use figment::{Figment, providers::{Env, Serialized}};
 use reqwest::Client;
+use thiserror::Error;
 use tower::{limit::ConcurrencyLimitLayer, ServiceBuilder};
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use tracing_subscriber;
 
 
@@ -23,6 +29,14 @@ struct Config {
     ingest_timeout_ms: u64,
     ingest_retries: usize,
     api_port: u16,
+    // Per-Org-API-Keys als "token:org_id,token2:org2,..." - siehe `BearerTokenAuth`.
+    api_keys: String,
+    // Loki-Push-Endpoint (z.B. "http://loki:3100/loki/api/v1/push") für Live-Tail/Alerting
+    // in Grafana - leer lässt `push_to_loki` den Fan-out überspringen.
+    loki_url: String,
+    // Pfad einer Logdatei, in die Honeypots ohne `/ingest`-Unterstützung JSON-Zeilen anhängen -
+    // leer lässt `run_file_tail` unstarted (siehe `main`).
+    tail_path: String,
 }
 
 impl Default for Config {
@@ -35,6 +49,9 @@ impl Default for Config {
             ingest_timeout_ms: 5000,
             ingest_retries: 3,
             api_port: 8080,
+            api_keys: String::new(),
+            loki_url: String::new(),
+            tail_path: String::new(),
         }
     }
 }
@@ -60,6 +77,131 @@ struct HealthResponse {
     ok: bool,
 }
 
+// Ersetzt die bisherigen Ad-hoc-`String`-Fehler: jede Variante trägt genug Kontext, um eine
+// passende HTTP-Antwort zu bauen, und `is_retryable` entscheidet, ob sich ein weiterer
+// Backoff-Versuch überhaupt lohnt (eine 4xx-Query gegen ClickHouse wird beim Retry nicht
+// plötzlich gültig).
+#[derive(Debug, Error)]
+enum IngestError {
+    #[error("ingest request contained no valid events")]
+    EmptyBatch,
+    #[error("invalid JSON in ingest payload: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("ClickHouse rejected the insert (status {status}): {body}")]
+    ClickHouseStatus { status: u16, body: String },
+    #[error("transport error contacting ClickHouse: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("timed out waiting for ClickHouse")]
+    Timeout,
+}
+
+impl IngestError {
+    // Transportfehler und Timeouts sind naturgemäß transient; bei einer ClickHouse-Antwort
+    // zählt nur ein 5xx-Status als transient - eine 4xx-Query (Syntax, unbekannte Tabelle, ...)
+    // bleibt bei jedem Versuch gleich ungültig.
+    fn is_retryable(&self) -> bool {
+        match self {
+            IngestError::Transport(_) | IngestError::Timeout => true,
+            IngestError::ClickHouseStatus { status, .. } => *status >= 500,
+            IngestError::EmptyBatch | IngestError::InvalidJson(_) => false,
+        }
+    }
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            IngestError::EmptyBatch | IngestError::InvalidJson(_) => StatusCode::BAD_REQUEST,
+            IngestError::ClickHouseStatus { .. } | IngestError::Transport(_) => StatusCode::BAD_GATEWAY,
+            IngestError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+// `/ingest` vertraute bisher blind dem `org_id`-Feld im Body - jeder Sensor konnte damit Events
+// für jeden anderen Tenant fälschen. `OrgContext` ist die aus dem Request authentifizierte
+// Identität, gegen die der Handler das `org_id`-Feld jedes Events validiert bzw. überschreibt.
+#[derive(Debug, Clone)]
+struct OrgContext {
+    org_id: String,
+}
+
+#[derive(Debug, Error)]
+enum AuthError {
+    #[error("missing Authorization header")]
+    MissingHeader,
+    #[error("Authorization header is not a valid bearer token")]
+    MalformedHeader,
+    #[error("unknown or revoked API key")]
+    InvalidToken,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
+    }
+}
+
+// Als Trait modelliert, damit sich künftig mTLS oder HMAC-signierte Sensor-Submissions
+// einhängen lassen, ohne `ingest_handler` anzufassen.
+#[async_trait]
+trait ApiAuth: Send + Sync {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<OrgContext, AuthError>;
+}
+
+// Standard-Implementierung: prüft `Authorization: Bearer <token>` gegen eine aus der
+// Konfiguration geladene Token→Org-Tabelle.
+struct BearerTokenAuth {
+    keys_by_token: HashMap<String, String>,
+}
+
+impl BearerTokenAuth {
+    // Erwartet `API_KEYS` im Format "token:org_id,token2:org2,...".
+    fn from_config(raw: &str) -> Self {
+        let keys_by_token = raw
+            .split(',')
+            .filter_map(|pair| pair.trim().split_once(':'))
+            .map(|(token, org_id)| (token.to_string(), org_id.to_string()))
+            .collect();
+        Self { keys_by_token }
+    }
+}
+
+#[async_trait]
+impl ApiAuth for BearerTokenAuth {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<OrgContext, AuthError> {
+        let header_value = headers
+            .get(axum::http::header::AUTHORIZATION)
+            .ok_or(AuthError::MissingHeader)?
+            .to_str()
+            .map_err(|_| AuthError::MalformedHeader)?;
+
+        let token = header_value.strip_prefix("Bearer ").ok_or(AuthError::MalformedHeader)?;
+
+        self.keys_by_token
+            .get(token)
+            .map(|org_id| OrgContext { org_id: org_id.clone() })
+            .ok_or(AuthError::InvalidToken)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for OrgContext {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        state.auth.authenticate(&parts.headers).await
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Config,
+    client: Client,
+    auth: Arc<dyn ApiAuth>,
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
@@ -77,7 +219,16 @@ async fn main() {
         .build()
         .unwrap();
 
-    let shared = Arc::new((config.clone(), client));
+    let auth: Arc<dyn ApiAuth> = Arc::new(BearerTokenAuth::from_config(&config.api_keys));
+    let shared = Arc::new(AppState { config: config.clone(), client, auth });
+
+    // Honeypots, die nicht direkt an `/ingest` POSTen, hängen JSON-Zeilen an eine Logdatei an -
+    // läuft als Hintergrund-Task neben dem axum-Server, solange `tail_path` konfiguriert ist.
+    if !config.tail_path.is_empty() {
+        let tail_state = shared.clone();
+        let tail_path = config.tail_path.clone();
+        tokio::spawn(run_file_tail(tail_path, tail_state));
+    }
 
     let app = Router::new()
         .route("/ingest", post(ingest_handler))
@@ -93,24 +244,129 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
+// Gruppiert Events nach (org_id, sensor_id, event_type, severity) zu Loki-Streams, da Loki
+// ein Label-Set pro Stream erwartet statt einer flachen Log-Zeile pro Event.
+fn build_loki_push_body(events: &[IngestEvent]) -> serde_json::Value {
+    let mut streams: HashMap<(String, String, String, String), Vec<[String; 2]>> = HashMap::new();
+
+    for event in events {
+        let key = (event.org_id.clone(), event.sensor_id.clone(), event.event_type.clone(), event.severity.clone());
+        let ts_ns = parse_ts_ns(event.ts_str.as_deref());
+        let line = serde_json::to_string(event).unwrap_or_default();
+        streams.entry(key).or_default().push([ts_ns.to_string(), line]);
+    }
+
+    let streams_json: Vec<serde_json::Value> = streams
+        .into_iter()
+        .map(|((org_id, sensor_id, event_type, severity), values)| {
+            json!({
+                "stream": {
+                    "org_id": org_id,
+                    "sensor_id": sensor_id,
+                    "event_type": event_type,
+                    "severity": severity,
+                },
+                "values": values,
+            })
+        })
+        .collect();
+
+    json!({ "streams": streams_json })
+}
+
+// `ts_str` wird, wenn vorhanden, als RFC3339 geparst; ein fehlendes oder ungültiges
+// Zeitstempel-Feld fällt auf "jetzt" zurück, statt das Event zu verwerfen.
+fn parse_ts_ns(ts_str: Option<&str>) -> u128 {
+    ts_str
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .and_then(|dt| dt.timestamp_nanos_opt())
+        .map(|ns| ns as u128)
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        })
+}
+
+// Fan-out nach einem erfolgreichen ClickHouse-Insert - Loki ist nur für Live-Tail/Alerting
+// gedacht, keine Source of Truth, daher darf ein Fehler hier den Ingest-Request nicht scheitern
+// lassen, sondern wird nur geloggt.
+//
+// `/loki/api/v1/push` akzeptiert unter `Content-Type: application/x-protobuf` ausschließlich ein
+// Snappy-komprimiertes `logproto.PushRequest`-Protobuf - kein Snappy-komprimiertes JSON. Ohne den
+// Protobuf-Codec zur Verfügung zu haben, nutzen wir stattdessen Lokis reinen JSON-Push-Endpoint
+// (`Content-Type: application/json`, unkomprimiert).
+async fn push_to_loki(client: &Client, loki_url: &str, events: &[IngestEvent]) {
+    if loki_url.is_empty() {
+        return;
+    }
+
+    let body = build_loki_push_body(events);
+
+    match client
+        .post(loki_url)
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await
+    {
+        Ok(r) if r.status().is_success() => {}
+        Ok(r) => warn!("Loki: Push abgelehnt (Status {})", r.status()),
+        Err(e) => warn!("Loki: Push fehlgeschlagen: {:?}", e),
+    }
+}
+
 async fn ingest_handler(
-    State(state): State<Arc<(Config, Client)>>,
+    State(state): State<Arc<AppState>>,
+    org: OrgContext,
     body: axum::body::Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let (config, client) = &*state;
+) -> Result<StatusCode, IngestError> {
+    let AppState { config, client, .. } = &*state;
     let body_str = String::from_utf8_lossy(&body);
 
-    // Versuche als JSON-Array zu parsen
-    let events: Result<Vec<IngestEvent>, _> = serde_json::from_str(&body_str);
-    let lines: Vec<String> = if let Ok(evts) = events {
-        // JSON-Array → JSONEachRow
-        evts.into_iter().map(|e| serde_json::to_string(&e).unwrap()).collect()
-    } else {
-        // Versuche als JSONEachRow (newline-delimited JSON)
-        body_str.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect()
+    // Versuche zunächst als JSON-Array zu parsen, sonst als JSONEachRow (newline-delimited JSON) -
+    // schlägt eine einzelne NDJSON-Zeile fehl, ist das eine echte, meldenswerte `InvalidJson`,
+    // kein stiller Teilverlust.
+    let mut events: Vec<IngestEvent> = match serde_json::from_str(&body_str) {
+        Ok(evts) => evts,
+        Err(array_err) => {
+            let non_empty_lines: Vec<&str> = body_str.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+            if non_empty_lines.is_empty() {
+                Vec::new()
+            } else {
+                let mut parsed = Vec::with_capacity(non_empty_lines.len());
+                for line in non_empty_lines {
+                    match serde_json::from_str::<IngestEvent>(line) {
+                        Ok(event) => parsed.push(event),
+                        Err(_) => return Err(IngestError::InvalidJson(array_err)),
+                    }
+                }
+                parsed
+            }
+        }
     };
+
+    // Jeder Sensor authentifiziert sich als genau eine Org - ein abweichendes `org_id` im Body
+    // wird durch die verifizierte Identität ersetzt, statt dem Client zu vertrauen.
+    for event in &mut events {
+        if event.org_id != org.org_id {
+            warn!("Ingest: org_id '{}' im Payload weicht von authentifizierter Org '{}' ab, überschreibe", event.org_id, org.org_id);
+            event.org_id = org.org_id.clone();
+        }
+    }
+
+    insert_events(client, config, &events).await?;
+    Ok(StatusCode::OK)
+}
+
+// Batch-Insert nach ClickHouse mit Retry/Backoff - gemeinsam genutzt von `ingest_handler`
+// (HTTP-POST) und `run_file_tail` (Logdatei-Tailing), damit beide Ingest-Wege dieselben
+// Garantien (Retry, `is_retryable`-Klassifizierung, Loki-Fan-out) bekommen.
+async fn insert_events(client: &Client, config: &Config, events: &[IngestEvent]) -> Result<(), IngestError> {
+    let lines: Vec<String> = events.iter().map(|e| serde_json::to_string(e).unwrap()).collect();
     if lines.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "No valid events".to_string()));
+        return Err(IngestError::EmptyBatch);
     }
 
     let insert_body = lines.join("\n");
@@ -124,26 +380,152 @@ async fn ingest_handler(
             .header("Content-Type", "application/json")
             .send()
             .await;
-        match resp {
+        let err = match resp {
             Ok(r) if r.status().is_success() => {
                 info!("Inserted {} events", lines.len());
-                return Ok(StatusCode::OK);
+                push_to_loki(client, &config.loki_url, events).await;
+                return Ok(());
             }
-            Ok(r) => {
-                last_err = Some(format!("ClickHouse error: {}", r.text().await.unwrap_or_default()));
+            Ok(r) => IngestError::ClickHouseStatus {
+                status: r.status().as_u16(),
+                body: r.text().await.unwrap_or_default(),
+            },
+            Err(e) if e.is_timeout() => IngestError::Timeout,
+            Err(e) => IngestError::Transport(e),
+        };
+
+        // Eine 4xx-Query bleibt bei jedem weiteren Versuch gleich ungültig - den
+        // Backoff-Loop dafür durchzuziehen würde nur Latenz ohne Aussicht auf Erfolg kosten.
+        if !err.is_retryable() {
+            error!("Insert endgültig fehlgeschlagen (nicht retrybar): {}", err);
+            return Err(err);
+        }
+        last_err = Some(err);
+        tokio::time::sleep(Duration::from_millis(100 * 2u64.pow(attempt as u32))).await;
+    }
+
+    let err = last_err.unwrap_or(IngestError::Timeout);
+    error!("Insert nach {} Versuchen fehlgeschlagen: {}", config.ingest_retries, err);
+    Err(err)
+}
+
+// Nicht jeder Honeypot kann an `/ingest` POSTen - manche hängen nur JSON-Zeilen an eine
+// Logdatei an. Dieser Hintergrund-Task beobachtet diese Datei und inserted neue Zeilen über
+// denselben `insert_events`-Pfad wie `ingest_handler`, inklusive Retry/Backoff.
+async fn run_file_tail(path: String, state: Arc<AppState>) {
+    use notify::event::ModifyKind;
+    use notify::{Event, EventKind, RecursiveMode, Watcher};
+    use std::io::{Read, Seek, SeekFrom};
+
+    let (tx, rx) = std::sync::mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            error!("Tail: Watcher konnte nicht erstellt werden: {:?}", e);
+            return;
+        }
+    };
+
+    let watch_path = std::path::PathBuf::from(&path);
+    let watch_dir = watch_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+        error!("Tail: Verzeichnis {:?} konnte nicht beobachtet werden: {:?}", watch_dir, e);
+        return;
+    }
+
+    // Beim Start wird nur auf neu angehängte Zeilen reagiert, nicht der gesamte bestehende
+    // Dateiinhalt nochmal eingelesen.
+    let mut offset = std::fs::metadata(&watch_path).map(|m| m.len()).unwrap_or(0);
+    info!("Tail: beobachte {:?} ab Offset {}", watch_path, offset);
+
+    let mut rx = rx;
+    loop {
+        let recv_result = match tokio::task::spawn_blocking(move || {
+            let result = rx.recv();
+            (result, rx)
+        })
+        .await
+        {
+            Ok((result, returned_rx)) => {
+                rx = returned_rx;
+                result
             }
             Err(e) => {
-                last_err = Some(format!("Request error: {}", e));
+                error!("Tail: Blocking-Task abgebrochen: {:?}", e);
+                break;
             }
+        };
+
+        let event = match recv_result {
+            Ok(Ok(event)) => event,
+            Ok(Err(e)) => {
+                error!("Tail: Watcher-Fehler: {:?}", e);
+                continue;
+            }
+            Err(_) => break, // Watcher-Channel geschlossen
+        };
+
+        if !event.paths.iter().any(|p| p == &watch_path) {
+            continue;
+        }
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Data(_)) => {
+                let mut file = match std::fs::File::open(&watch_path) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        error!("Tail: {:?} konnte nicht geöffnet werden: {:?}", watch_path, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+                    error!("Tail: Seek in {:?} fehlgeschlagen: {:?}", watch_path, e);
+                    continue;
+                }
+                let mut buf = String::new();
+                if let Err(e) = file.read_to_string(&mut buf) {
+                    error!("Tail: Lesen aus {:?} fehlgeschlagen: {:?}", watch_path, e);
+                    continue;
+                }
+                offset += buf.len() as u64;
+
+                let new_events: Vec<IngestEvent> = buf
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .filter_map(|line| match serde_json::from_str::<IngestEvent>(line) {
+                        Ok(event) => Some(event),
+                        Err(e) => {
+                            warn!("Tail: ungültige Zeile übersprungen: {:?}", e);
+                            None
+                        }
+                    })
+                    .collect();
+
+                if !new_events.is_empty() {
+                    if let Err(e) = insert_events(&state.client, &state.config, &new_events).await {
+                        error!("Tail: Insert fehlgeschlagen: {}", e);
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                // Logrotate o.ä.: Datei wurde umbenannt/ersetzt - von vorne beobachten.
+                info!("Tail: {:?} wurde rotiert, lese von vorne", watch_path);
+                offset = 0;
+            }
+            _ => {}
         }
-        tokio::time::sleep(Duration::from_millis(100 * 2u64.pow(attempt as u32))).await;
     }
-    error!("Insert failed: {:?}", last_err);
-    Err((StatusCode::BAD_GATEWAY, last_err.unwrap_or_else(|| "Unknown error".to_string())))
 }
 
-async fn health_handler(State(state): State<Arc<(Config, Client)>>) -> Result<Json<HealthResponse>, StatusCode> {
-    let (config, client) = &*state;
+async fn health_handler(State(state): State<Arc<AppState>>) -> Result<Json<HealthResponse>, IngestError> {
+    let AppState { config, client, .. } = &*state;
     let url_version = format!("{}/?user={}&password={}&database={}&query=SELECT version()", config.ch_http, config.ch_user, config.ch_pass, config.ch_db);
     let url_count = format!("{}/?user={}&password={}&database={}&query=SELECT count() FROM events", config.ch_http, config.ch_user, config.ch_pass, config.ch_db);
     let version = match client.get(&url_version).send().await {
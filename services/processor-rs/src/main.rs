@@ -1,11 +1,139 @@
-use tokio::time::{sleep, Duration};
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{env, net::SocketAddr, time::Duration};
+use tokio::sync::mpsc;
+use tokio::time::interval;
+
+// Ein einzelnes Honeypot-/Sensor-Event, wie es auf den Event-Bus gelegt wird.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Event {
+    ts: String,
+    org_id: String,
+    sensor_id: String,
+    event_type: String,
+    src_ip: String,
+    src_port: u16,
+    dst_port: u16,
+    proto: String,
+    severity: String,
+}
+
+#[derive(Clone)]
+struct AppState {
+    tx: mpsc::Sender<Event>,
+}
+
+const BATCH_MAX_SIZE: usize = 200;
+const BATCH_MAX_INTERVAL: Duration = Duration::from_secs(5);
+
+// Verbindungsdaten für den ClickHouse-Bulk-Insert in `flush_batch`, aus der Umgebung
+// konfigurierbar statt fest codiert (analog zur `Config` in `services/api-rs`).
+#[derive(Clone)]
+struct ClickHouseConfig {
+    http: String,
+    user: String,
+    pass: String,
+    db: String,
+}
+
+impl Default for ClickHouseConfig {
+    fn default() -> Self {
+        Self {
+            http: env::var("CH_HTTP").unwrap_or_else(|_| "http://localhost:8123".to_string()),
+            user: env::var("CH_USER").unwrap_or_else(|_| "default".to_string()),
+            pass: env::var("CH_PASS").unwrap_or_default(),
+            db: env::var("CH_DB").unwrap_or_else(|_| "default".to_string()),
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    println!("Processor up (stub) – wartet auf Events (später NATS/CH) …");
+    // Honeypots/Sensoren publizieren auf diesen Channel statt synchron zu inserten -
+    // der Batcher unten konsumiert ihn und entkoppelt Ingest-Latenz von der Senke.
+    let (tx, rx) = mpsc::channel::<Event>(1024);
+    tokio::spawn(run_batcher(rx, Client::new(), ClickHouseConfig::default()));
+
+    let state = AppState { tx };
+    let app = Router::new()
+        .route("/events", post(ingest_event))
+        .with_state(state);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], 9000));
+    println!("Processor lauscht auf http://{}/events (Event-Bus)", addr);
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+async fn ingest_event(State(state): State<AppState>, Json(event): Json<Event>) -> StatusCode {
+    match state.tx.send(event).await {
+        Ok(_) => StatusCode::ACCEPTED,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+// Sammelt eingehende Events zu Batches (Größe ODER Zeitfenster, je nachdem was zuerst
+// eintritt) und übergibt jeden Batch als einen einzigen Bulk-Insert.
+async fn run_batcher(mut rx: mpsc::Receiver<Event>, client: Client, ch_config: ClickHouseConfig) {
+    let mut buffer = Vec::with_capacity(BATCH_MAX_SIZE);
+    let mut ticker = interval(BATCH_MAX_INTERVAL);
+
     loop {
-        // Platzhalter-Work: später Batch/Korrelation/Insert
-        sleep(Duration::from_secs(5)).await;
-        println!("Processor heartbeat");
+        tokio::select! {
+            maybe_event = rx.recv() => {
+                match maybe_event {
+                    Some(event) => {
+                        buffer.push(event);
+                        if buffer.len() >= BATCH_MAX_SIZE {
+                            flush_batch(&mut buffer, &client, &ch_config).await;
+                        }
+                    }
+                    None => {
+                        flush_batch(&mut buffer, &client, &ch_config).await;
+                        break;
+                    }
+                }
+            }
+            _ = ticker.tick() => {
+                flush_batch(&mut buffer, &client, &ch_config).await;
+            }
+        }
     }
 }
+
+// Bulk-Insert des gesammelten Batches nach ClickHouse (JSONEachRow) - ein Request pro Batch
+// statt pro Event, und der Ort, an dem Korrelation über Honeypot-Typen hinweg ansetzen kann.
+// Ein fehlgeschlagener Insert wird nur geloggt statt den Batcher abzubrechen, der Batch ist
+// damit verloren (kein Retry hier, anders als `insert_events` in `services/api-rs`; einen
+// Spool gibt es in keinem der beiden).
+async fn flush_batch(buffer: &mut Vec<Event>, client: &Client, ch_config: &ClickHouseConfig) {
+    if buffer.is_empty() {
+        return;
+    }
+
+    let insert_body = buffer
+        .iter()
+        .map(|event| serde_json::to_string(event).unwrap_or_default())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let url = format!(
+        "{}/?user={}&password={}&database={}&query=INSERT INTO events FORMAT JSONEachRow",
+        ch_config.http, ch_config.user, ch_config.pass, ch_config.db
+    );
+
+    match client.post(&url).header("Content-Type", "application/json").body(insert_body).send().await {
+        Ok(r) if r.status().is_success() => {
+            println!("Processor: {} Events nach ClickHouse inserted", buffer.len());
+        }
+        Ok(r) => {
+            eprintln!("Processor: ClickHouse hat den Insert abgelehnt (Status {})", r.status());
+        }
+        Err(e) => {
+            eprintln!("Processor: ClickHouse-Insert fehlgeschlagen: {:?}", e);
+        }
+    }
+
+    buffer.clear();
+}
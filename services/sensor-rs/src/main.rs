@@ -1,5 +1,6 @@
 use chrono::Utc;
 use serde::Serialize;
+use std::env;
 use tokio::time::{sleep, Duration};
 
 #[derive(Serialize)]
@@ -17,7 +18,11 @@ struct Event {
 
 #[tokio::main]
 async fn main() {
-    println!("Sensor up (stub) – erzeugt Dummy-Events (stdout) …");
+    let processor_url =
+        env::var("PROCESSOR_URL").unwrap_or_else(|_| "http://localhost:9000/events".to_string());
+    let client = reqwest::Client::new();
+
+    println!("Sensor up – publiziert Dummy-Events an {} …", processor_url);
     loop {
         let e = Event {
             ts: Utc::now().to_rfc3339(),
@@ -30,7 +35,19 @@ async fn main() {
             proto: "tcp",
             severity: "low",
         };
-        println!("{}", serde_json::to_string(&e).unwrap());
+
+        match client.post(&processor_url).json(&e).send().await {
+            Ok(res) if res.status().is_success() => {
+                println!("Event gesendet: {}", serde_json::to_string(&e).unwrap());
+            }
+            Ok(res) => {
+                eprintln!("Processor lehnte Event ab: {}", res.status());
+            }
+            Err(err) => {
+                eprintln!("Konnte Event nicht an Processor senden: {:?}", err);
+            }
+        }
+
         sleep(Duration::from_secs(3)).await;
     }
 }